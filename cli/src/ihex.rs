@@ -0,0 +1,197 @@
+/// A contiguous block of data that has to be placed at `address`.
+#[derive(Debug, PartialEq)]
+pub struct Chunk {
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum HexError {
+    /// The line with the given number does not start with a colon.
+    MissingStartCode(usize),
+    /// The line with the given number contains characters that are not hex digits.
+    InvalidCharacter(usize),
+    /// The byte count of the record on the given line does not match its length.
+    InvalidLength(usize),
+    /// The checksum of the record on the given line is wrong.
+    ChecksumMismatch(usize),
+    /// The record on the given line has an unknown type.
+    UnknownRecordType(usize, u8),
+    /// The file ended without an end of file record.
+    MissingEndOfFile,
+}
+
+/// Parses the contents of an Intel HEX file into chunks of contiguous data.
+///
+/// Extended segment and extended linear address records are honored,
+/// start address records are ignored as they don't carry any data.
+pub fn parse(contents: &str) -> Result<Vec<Chunk>, HexError> {
+    let mut chunks: Vec<Chunk> = vec![];
+    let mut base_address = 0u32;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(':') {
+            return Err(HexError::MissingStartCode(line_number));
+        }
+        let record = &line[1..];
+        // Records are sliced by byte index below, which only works on ASCII.
+        if !record.is_ascii() {
+            return Err(HexError::InvalidCharacter(line_number));
+        }
+        if record.len() % 2 != 0 || record.len() < 10 {
+            return Err(HexError::InvalidLength(line_number));
+        }
+        let bytes = (0..record.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&record[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| HexError::InvalidCharacter(line_number))?;
+
+        // Byte count, address (2 bytes), record type, data and checksum.
+        let count = bytes[0] as usize;
+        if bytes.len() != count + 5 {
+            return Err(HexError::InvalidLength(line_number));
+        }
+        if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(HexError::ChecksumMismatch(line_number));
+        }
+        let offset = (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        let data = &bytes[4..4 + count];
+
+        match bytes[3] {
+            // Data
+            0x00 => {
+                let address = base_address.wrapping_add(offset);
+                match chunks.last_mut() {
+                    // Compared in 64 bits, as a chunk may end exactly at the top of the address space.
+                    Some(ref mut chunk) if u64::from(chunk.address) + chunk.data.len() as u64 == u64::from(address) => {
+                        chunk.data.extend_from_slice(data)
+                    },
+                    _ => chunks.push(Chunk { address, data: data.to_vec() }),
+                }
+            },
+            // End of file
+            0x01 => return Ok(chunks),
+            // Extended segment address
+            0x02 if count == 2 => base_address = ((u32::from(data[0]) << 8) | u32::from(data[1])) << 4,
+            // Extended linear address
+            0x04 if count == 2 => base_address = ((u32::from(data[0]) << 8) | u32::from(data[1])) << 16,
+            0x02 | 0x04 => return Err(HexError::InvalidLength(line_number)),
+            // Start segment address and start linear address
+            0x03 | 0x05 => (),
+            record_type => return Err(HexError::UnknownRecordType(line_number, record_type)),
+        }
+    }
+
+    Err(HexError::MissingEndOfFile)
+}
+
+/// Serializes `data` placed at `address` into the contents of an Intel HEX file.
+///
+/// Data records hold up to 16 bytes and extended linear address records are emitted whenever
+/// the upper 16 bits of the address change.
+pub fn serialize(address: u32, data: &[u8]) -> String {
+    let mut contents = String::new();
+    let mut upper_address = None;
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let record_address = address.wrapping_add(offset as u32);
+        if upper_address != Some(record_address >> 16) {
+            upper_address = Some(record_address >> 16);
+            contents.push_str(&record(0x04, 0, &[(record_address >> 24) as u8, (record_address >> 16) as u8]));
+        }
+        // A data record must not cross a 64KB boundary as its address is only 16 bits wide.
+        let to_boundary = 0x1_0000 - (record_address & 0xFFFF) as usize;
+        let length = std::cmp::min(std::cmp::min(16, data.len() - offset), to_boundary);
+        contents.push_str(&record(0x00, record_address as u16, &data[offset..offset + length]));
+        offset += length;
+    }
+
+    contents.push_str(&record(0x01, 0, &[]));
+    contents
+}
+
+/// Formats a single record including its start code, checksum and line ending.
+fn record(record_type: u8, offset: u16, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8, (offset >> 8) as u8, offset as u8, record_type];
+    bytes.extend_from_slice(data);
+    let checksum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)).wrapping_neg();
+    bytes.push(checksum);
+
+    let mut line = String::from(":");
+    for byte in bytes {
+        line.push_str(&format!("{:02X}", byte));
+    }
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, record, serialize, Chunk, HexError};
+
+    #[test]
+    fn serialize_end_of_file() {
+        debug_assert_eq!(serialize(0x0, &[]), ":00000001FF\n");
+    }
+
+    #[test]
+    fn serialize_across_64k_boundary() {
+        let data = (0..32).collect::<Vec<u8>>();
+        let contents = serialize(0x0800_FFF8, &data);
+        debug_assert_eq!(contents.lines().next(), Some(":020000040800F2"));
+        debug_assert_eq!(parse(&contents).unwrap(), vec![Chunk { address: 0x0800_FFF8, data }]);
+    }
+
+    #[test]
+    fn parse_contiguous_records() {
+        let chunks = parse(":0400000001020304F2\n:02000400AABB95\n:00000001FF\n").unwrap();
+        debug_assert_eq!(chunks, vec![Chunk { address: 0x0, data: vec![0x01, 0x02, 0x03, 0x04, 0xAA, 0xBB] }]);
+    }
+
+    #[test]
+    fn parse_extended_linear_address() {
+        let chunks = parse(":020000040800F2\n:0400000001020304F2\n:00000001FF\n").unwrap();
+        debug_assert_eq!(chunks, vec![Chunk { address: 0x0800_0000, data: vec![0x01, 0x02, 0x03, 0x04] }]);
+    }
+
+    #[test]
+    fn parse_chunk_ending_at_top_of_address_space() {
+        let contents = [
+            record(0x04, 0, &[0xFF, 0xFF]),
+            record(0x00, 0xFFF0, &[0xAA; 16]),
+            record(0x00, 0x0000, &[0x55]),
+            record(0x01, 0, &[]),
+        ].concat();
+        let chunks = parse(&contents).unwrap();
+        debug_assert_eq!(chunks, vec![
+            Chunk { address: 0xFFFF_FFF0, data: vec![0xAA; 16] },
+            Chunk { address: 0xFFFF_0000, data: vec![0x55] },
+        ]);
+    }
+
+    #[test]
+    fn parse_bad_checksum_should_error() {
+        debug_assert!(parse(":0400000001020304F3\n:00000001FF\n").is_err());
+    }
+
+    #[test]
+    fn parse_missing_end_of_file_should_error() {
+        debug_assert!(parse(":0400000001020304F2\n").is_err());
+    }
+
+    #[test]
+    fn parse_non_ascii_character_should_error() {
+        match parse(":0\u{e4}0000001FF\n") {
+            Err(HexError::InvalidCharacter(1)) => (),
+            other => panic!("Expected an invalid character error, got {:?}", other),
+        }
+    }
+}
@@ -1,9 +1,14 @@
-use std::io::Write;
-use memory::MI;
+mod ihex;
+
+use memory::{MI, MI32};
+use memory::memory_interface::ADIMemoryInterface;
+use memory::romtable::{read_component_tree, Component, RomTableError};
 use coresight::ap_access::APAccess;
-use coresight::access_ports::generic_ap::GenericAP;
+use coresight::access_ports::generic_ap::{APClass, GenericAP};
 use coresight::ap_access::access_port_is_valid;
 use coresight::access_ports::AccessPortError;
+use coresight::chip_info::ChipInfo;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use coresight::dap_access::DAPAccess;
@@ -49,19 +54,37 @@ enum CLI {
         loc: u32,
         /// The amount of memory (in words) to dump
         words: u32,
+        /// Write the memory to this file instead of printing it. Files ending in .hex are written as Intel HEX, all others as raw binary
+        #[structopt(long = "output", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Download an Intel HEX or raw binary image to the RAM of the attached target. Flash cannot be programmed this way, so the written memory is read back and verified
+    #[structopt(name = "download")]
+    Download {
+        /// The number associated with the ST-Link to use
+        n: u8,
+        /// The path to the file to download
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// Download the file as raw binary to this address (in hexadecimal without 0x prefix) instead of parsing it as Intel HEX
+        #[structopt(long = "base", parse(try_from_str = "parse_hex"))]
+        base: Option<u32>,
+        /// The number of bytes to skip at the start of a raw binary file, only valid together with --base
+        #[structopt(long = "skip", default_value = "0")]
+        skip: usize,
+        /// Skip reading back the written memory and comparing it to the image
+        #[structopt(long = "no-verify")]
+        no_verify: bool,
+        /// Move the whole image by this offset (in hexadecimal without 0x prefix), e.g. to place it behind a bootloader
+        #[structopt(long = "offset", parse(try_from_str = "parse_hex"), default_value = "0")]
+        offset: u32,
+        /// Patch the vector table checksum expected by the boot ROM of NXP LPC parts
+        #[structopt(long = "lpc-checksum")]
+        lpc_checksum: bool,
+        /// Switch the wire protocol to this clock (in kHz) after attaching, the probe picks the closest slower setting
+        #[structopt(long = "speed")]
+        speed: Option<u32>,
     },
-    /// Download memory to attached target
-    // #[structopt(name = "download")]
-    // Download {
-    //     /// The number associated with the ST-Link to use
-    //     n: u8,
-    //     /// The address of the memory to download to the target (in hexadecimal without 0x prefix)
-    //     #[structopt(parse(try_from_str = "parse_hex"))]
-    //     loc: u32,
-    //     /// The the word to write to memory
-    //     #[structopt(parse(try_from_str = "parse_hex"))]
-    //     word: u32,
-    // },
     #[structopt(name = "trace")]
     Trace {
         /// The number associated with the ST-Link to use
@@ -79,8 +102,11 @@ fn main() {
         CLI::List {} => list_connected_devices(),
         CLI::Info { n } => show_info_of_device(n).unwrap(),
         CLI::Reset { n, assert } => reset_target_of_device(n, assert).unwrap(),
-        CLI::Dump { n, loc, words } => dump_memory(n, loc, words).unwrap(),
-        //CLI::Download { n, loc, word } => download(n, loc, word).unwrap(),
+        CLI::Dump { n, loc, words, output } => dump_memory(n, loc, words, output.as_deref()).unwrap(),
+        CLI::Download { n, path, base, skip, no_verify, offset, lpc_checksum, speed } => {
+            let options = DownloadOptions { base, skip, verify: !no_verify, offset, lpc_checksum, speed };
+            download(n, &path, &options).unwrap()
+        },
         CLI::Trace { n, loc } => trace_u32_on_target(n, loc).unwrap(),
     }
 }
@@ -110,6 +136,7 @@ fn list_connected_devices() {
 enum Error {
     DebugProbe(DebugProbeError),
     AccessPort(AccessPortError),
+    Hex(ihex::HexError),
     Custom(&'static str),
     StdIO(std::io::Error),
 }
@@ -153,37 +180,42 @@ fn show_info_of_device(n: u8) -> Result<(), Error> {
         println!("\nJTAG Version: {:?}", version.1);
         println!("\nTarget Voltage: {:?}", vtg);
 
-        st_link
-            .write_register(0xFFFF, 0x2, 0x2)
+        let idcode = st_link
+            .read_register(0xFFFF, 0x0)
             .or_local_err()?;
 
-        let target_info = st_link
-            .read_register(0xFFFF, 0x4)
-            .or_local_err()?;
-        let target_info = parse_target_id(target_info);
+        // TARGETID only exists from DPv2 on, in bank 2 at address 0x4.
         println!("\nTarget Identification Register (TARGETID):");
-        println!(
-            "\tRevision = {}, Part Number = {}, Designer = {}",
-            target_info.0, target_info.3, target_info.2
-        );
+        if (idcode >> 12) & 0xF >= 2 {
+            let target_id = st_link
+                .read_dp_banked(2, 0x4)
+                .or_local_err()?;
+            match ChipInfo::from_target_id(target_id) {
+                Some(chip) => println!("\tFound {} (0x{:08X})", chip, target_id),
+                None => println!("\tNo valid identification (0x{:08X})", target_id),
+            }
+        } else {
+            println!("\tNot available, the debug port is older than DPv2");
+        }
 
-        let target_info = st_link
-            .read_register(0xFFFF, 0x0)
-            .or_local_err()?;
-        let target_info = parse_target_id(target_info);
         println!("\nIdentification Code Register (IDCODE):");
-        println!(
-            "\tProtocol = {},\n\tPart Number = {},\n\tJEDEC Manufacturer ID = {:x}",
-            if target_info.0 == 0x4 {
-                "JTAG-DP"
-            } else if target_info.0 == 0x3 {
-                "SW-DP"
-            } else {
-                "Unknown Protocol"
-            },
-            target_info.1,
-            target_info.2
-        );
+        match ChipInfo::from_idcode(idcode) {
+            Some(info) => println!(
+                "\tProtocol = {},\n\tPart Number = {:02X},\n\tJEDEC Manufacturer ID = {:x}/{:02x} ({})",
+                if info.revision == 0x4 {
+                    "JTAG-DP"
+                } else if info.revision == 0x3 {
+                    "SW-DP"
+                } else {
+                    "Unknown Protocol"
+                },
+                info.part_number,
+                info.designer.cc,
+                info.designer.id,
+                info.designer_name().unwrap_or("Unknown")
+            ),
+            None => println!("\tNo valid identification (0x{:08X})", idcode),
+        }
 
         println!("\nAvailable Ports");
 
@@ -202,147 +234,230 @@ fn show_info_of_device(n: u8) -> Result<(), Error> {
                                   .or_local_err()?;
                 println!("{:#?}", base);
 
-                let mut data = vec![0 as u8; 1024];
-                st_link.read_block(base.BASEADDR, &mut data.as_mut_slice()).or_else(|e| Err(Error::AccessPort(e)))?;
-                println!("READ STUFF");
-                let mut file = std::fs::File::create("ROMtbl.bin").unwrap();
-                file.write_all(data.as_slice());
-
-                // CoreSight identification register offsets.
-                const DEVARCH: u32 = 0xfbc;
-                const DEVID: u32 = 0xfc8;
-                const DEVTYPE: u32 = 0xfcc;
-                const PIDR4: u32 = 0xfd0;
-                const PIDR0: u32 = 0xfe0;
-                const CIDR0: u32 = 0xff0;
-                const IDR_END: u32 = 0x1000;
-
-                // Range of identification registers to read at once and offsets in results.
-                //
-                // To improve component identification performance, we read all of a components
-                // CoreSight ID registers in a single read. Reading starts at the DEVARCH register.
-                const IDR_READ_START: u32 = DEVARCH;
-                const IDR_READ_COUNT: u32 = (IDR_END - IDR_READ_START) / 4;
-                const DEVARCH_OFFSET: u32 = (DEVARCH - IDR_READ_START) / 4;
-                const DEVTYPE_OFFSET: u32 = (DEVTYPE - IDR_READ_START) / 4;
-                const PIDR4_OFFSET: u32 = (PIDR4 - IDR_READ_START) / 4;
-                const PIDR0_OFFSET: u32 = (PIDR0 - IDR_READ_START) / 4;
-                const CIDR0_OFFSET: u32 = (CIDR0 - IDR_READ_START) / 4;
-
-                let cidr = extract_id_register_value(data.as_slice(), CIDR0_OFFSET);
-                println!("{:08X?}", cidr);
+                // Only MEM-APs with a debug entry have a ROM table, which is read through that same AP.
+                if idr.CLASS == APClass::MEMAP && base.P == 1 {
+                    let tree = ADIMemoryInterface::detect(st_link, port)
+                        .map_err(RomTableError::from)
+                        .and_then(|interface| read_component_tree(&mut interface.connect(st_link), base.BASEADDR));
+                    match tree {
+                        Ok(tree) => {
+                            println!("\nCoreSight components:");
+                            tree.walk(&mut |component, depth| print_component(component, depth));
+                        },
+                        Err(e) => println!("\nCould not read the ROM table at {:08X}: {:?}", base.BASEADDR, e),
+                    }
+                }
             }
         }
 
-        // TODO: seems broken
-        // if target_info.3 != 1
-        //     || !(target_info.0 == 0x3 || target_info.0 == 0x4)
-        //     || !(target_info.1 == 0xBA00 || target_info.1 == 0xBA02)
-        // {
-        //     return Err(Error::Custom(
-        //         "The IDCODE register has not-expected contents.",
-        //     ));
-        // }
         Ok(())
     })
 }
 
-fn extract_id_register_value(regs: &[u8], offset: u32) -> u32 {
-    let mut result = 0 as u32;
-    println!("{}", result);
-    for i in 0..4 {
-        let value = regs[offset as usize + i] as u32;
-        result |= (value & 0xff) << (i * 8);
+/// Prints a CoreSight component and the referenced components that could not be read, indented by `depth`.
+fn print_component(component: &Component, depth: usize) {
+    let id = &component.id;
+    println!(
+        "{:indent$}{:08X}: {:?}, Part Number = {:03X}, Revision = {}, Designer = {}",
+        "",
+        id.base_address,
+        id.class,
+        id.part_number,
+        id.revision,
+        id.designer_name().unwrap_or("Unknown"),
+        indent = depth * 2
+    );
+    for (address, error) in &component.failed {
+        println!("{:indent$}{:08X}: Could not be read: {:?}", "", address, error, indent = (depth + 1) * 2);
     }
-    return result
-}
-
-// revision | partno | designer | reserved
-// 4 bit    | 16 bit | 11 bit   | 1 bit
-fn parse_target_id(value: u32) -> (u8, u16, u16, u8) {
-    (
-        (value >> 28) as u8,
-        (value >> 12) as u16,
-        ((value >> 1) & 0x07FF) as u16,
-        (value & 0x01) as u8,
-    )
 }
 
-fn dump_memory(n: u8, loc: u32, words: u32) -> Result<(), Error> {
+fn dump_memory(n: u8, loc: u32, words: u32, output: Option<&Path>) -> Result<(), Error> {
     with_device(n, |st_link| {
         let mut data = vec![0 as u32; words as usize];
 
         // Start timer.
+        st_link.reset_statistics();
         let instant = Instant::now();
 
-        st_link.read_block(loc, &mut data.as_mut_slice()).or_else(|e| Err(Error::AccessPort(e)))?;
+        st_link.read_block_at(loc, &mut data.as_mut_slice()).or_else(|e| Err(Error::AccessPort(e)))?;
         // Stop timer.
         let elapsed = instant.elapsed();
 
-        // Print read values.
-        for word in 0..words {
-            println!("Addr 0x{:08x?}: 0x{:08x}", loc + 4 * word, data[word as usize]);
+        if let Some(path) = output {
+            // The target is little endian, so this gives the bytes in memory order.
+            let bytes = data.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+            let is_hex = path.extension().and_then(|extension| extension.to_str()) == Some("hex");
+            if is_hex {
+                std::fs::write(path, ihex::serialize(loc, &bytes))
+            } else {
+                std::fs::write(path, &bytes)
+            }.map_err(Error::StdIO)?;
+            println!("Wrote memory to {}", path.display());
+        } else {
+            // Print read values.
+            for word in 0..words {
+                println!("Addr 0x{:08x?}: 0x{:08x}", loc + 4 * word, data[word as usize]);
+            }
         }
         // Print stats.
         println!("Read {:?} words in {:?}", words, elapsed);
+        print_statistics(&st_link.statistics());
 
         Ok(())
     })
 }
 
-// TODO: highly unfinished.
-// fn download(n: u8, loc: u32, word: u32) -> Result<(), Error> {
-//     const CSW_SIZE32: u32 = 0x00000002;
-//     const CSW_SADDRINC: u32 = 0x00000010;
-//     const CSW_DBGSTAT: u32 = 0x00000040;
-//     const CSW_HPROT: u32 = 0x02000000;
-//     const CSW_MSTRDBG: u32 = 0x20000000;
-//     const CSW_RESERVED: u32 = 0x01000000;
+/// Prints the USB traffic that was needed for an operation.
+fn print_statistics(statistics: &stlink::TransferStatistics) {
+    println!(
+        "USB: {:?} commands ({:?} failed), {:?} bytes out, {:?} bytes in, {:?} in transfers",
+        statistics.commands,
+        statistics.failed_commands,
+        statistics.bytes_written,
+        statistics.bytes_read,
+        statistics.transfer_time
+    );
+}
 
-//     const CSW_VALUE: u32 = (CSW_RESERVED | CSW_MSTRDBG | CSW_HPROT | CSW_DBGSTAT | CSW_SADDRINC);
+/// Loads the image at `path` into chunks of contiguous data.
+/// If `base` is given the file is treated as raw binary placed at `base`, with the first `skip` bytes left out.
+/// Otherwise the file is parsed as Intel HEX, where skipping bytes is not supported.
+fn load_image(path: &Path, base: Option<u32>, skip: usize) -> Result<Vec<ihex::Chunk>, Error> {
+    if let Some(address) = base {
+        let data = std::fs::read(path).map_err(Error::StdIO)?;
+        if skip > data.len() {
+            return Err(Error::Custom("The number of bytes to skip is larger than the file."));
+        }
+        if u64::from(address) + (data.len() - skip) as u64 > 0x1_0000_0000 {
+            return Err(Error::Custom("The image does not fit into the 32 bit address space."));
+        }
+        Ok(vec![ihex::Chunk { address, data: data[skip..].to_vec() }])
+    } else {
+        if skip != 0 {
+            return Err(Error::Custom("Bytes can only be skipped in raw binary files, which requires a base address."));
+        }
+        let contents = std::fs::read_to_string(path).map_err(Error::StdIO)?;
+        ihex::parse(&contents).map_err(Error::Hex)
+    }
+}
 
-//     let mut context = libusb::Context::new().or_else(|e| {
-//         println!("Failed to open an USB context.");
-//         Err(Error::USB(e))
-//     })?;
-//     let mut connected_devices = stlink::get_all_plugged_devices(&mut context).or_else(|e| {
-//         println!("Failed to fetch plugged USB devices.");
-//         Err(Error::USB(e))
-//     })?;
-//     if connected_devices.len() <= n as usize {
-//         println!("The device with the given number was not found.");
-//         Err(Error::DeviceNotFound)
-//     } else {
-//         Ok(())
-//     }?;
-//     let usb_device = STLinkUSBDevice::new(|device| device.remove(n as usize));
-//     let mut st_link = stlink::STLink::new(usb_device);
-//     st_link.open().or_else(|e| Err(Error::DebugProbeError(e)))?;
+/// Moves all chunks by `offset`.
+fn offset_chunks(chunks: &mut [ihex::Chunk], offset: u32) -> Result<(), Error> {
+    for chunk in chunks {
+        chunk.address = chunk.address
+            .checked_add(offset)
+            .filter(|address| u64::from(*address) + chunk.data.len() as u64 <= 0x1_0000_0000)
+            .ok_or(Error::Custom("The image does not fit into the 32 bit address space after applying the offset."))?;
+    }
+    Ok(())
+}
 
-//     st_link
-//         .attach(probe::protocol::WireProtocol::Swd)
-//         .or_else(|e| Err(Error::DebugProbeError(e)))?;
+/// Patches the vector table checksum of NXP LPC parts at the start of the image.
+///
+/// The boot ROM of those parts only starts the image if the first eight words of the vector table sum up to zero,
+/// which is achieved by storing the two's complement of the sum of the first seven words in the eighth.
+fn patch_lpc_checksum(chunks: &mut [ihex::Chunk]) -> Result<(), Error> {
+    let chunk = chunks
+        .iter_mut()
+        .min_by_key(|chunk| chunk.address)
+        .filter(|chunk| chunk.data.len() >= 32)
+        .ok_or(Error::Custom("The image does not start with a complete vector table."))?;
+
+    let checksum = chunk.data[0..28]
+        .chunks(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .fold(0u32, |sum, word| sum.wrapping_add(word))
+        .wrapping_neg();
+    chunk.data[28..32].copy_from_slice(&checksum.to_le_bytes());
+    Ok(())
+}
 
-//     st_link
-//         .write_register(0x0, 0x0, CSW_VALUE | CSW_SIZE32)
-//         .ok();
+/// How an image is placed into memory by `download`, see `CLI::Download` for the meaning of each option.
+struct DownloadOptions {
+    base: Option<u32>,
+    skip: usize,
+    verify: bool,
+    offset: u32,
+    lpc_checksum: bool,
+    speed: Option<u32>,
+}
 
-//     let mem = MemoryInterface::new(0x0);
+/// A byte that was read back with a different value than what was written.
+#[derive(Debug)]
+struct Mismatch {
+    address: u32,
+    expected: u8,
+    actual: u8,
+}
 
-//     let instant = Instant::now();
+/// Reads back the memory covered by `chunks` and returns all bytes that differ from the chunks' data.
+fn verify_chunks<M: MI>(memory: &mut M, chunks: &[ihex::Chunk]) -> Result<Vec<Mismatch>, Error> {
+    let mut mismatches = vec![];
+    for chunk in chunks {
+        let mut data = vec![0u8; chunk.data.len()];
+        memory.read_8(u64::from(chunk.address), data.as_mut_slice()).or_local_err()?;
+        for (offset, (expected, actual)) in chunk.data.iter().zip(data.iter()).enumerate() {
+            if expected != actual {
+                mismatches.push(Mismatch {
+                    address: chunk.address + offset as u32,
+                    expected: *expected,
+                    actual: *actual,
+                });
+            }
+        }
+    }
+    Ok(mismatches)
+}
 
-//     mem.write(&mut st_link, loc, word).or_else(|e| Err(Error::AccessPortError(e)))?;
+fn download(n: u8, path: &Path, options: &DownloadOptions) -> Result<(), Error> {
+    let mut chunks = load_image(path, options.base, options.skip)?;
+    offset_chunks(&mut chunks, options.offset)?;
+    if options.lpc_checksum {
+        patch_lpc_checksum(&mut chunks)?;
+    }
 
-//     let elapsed = instant.elapsed();
+    with_device(n, |st_link| {
+        if let Some(speed) = options.speed {
+            let speed = st_link.set_speed(speed).or_local_err()?;
+            println!("Protocol speed set to {:?} kHz", speed);
+        }
 
-//     println!("Addr 0x{:08x?}: 0x{:08x}", loc, word);
+        // Start timer.
+        st_link.reset_statistics();
+        let instant = Instant::now();
 
-//     println!("Wrote 1 word in {:?}", elapsed);
+        let mut bytes = 0;
+        for chunk in &chunks {
+            st_link.write_8(u64::from(chunk.address), chunk.data.as_slice()).or_local_err()?;
+            bytes += chunk.data.len();
+        }
+        // Stop timer.
+        let elapsed = instant.elapsed();
 
-//     st_link.close().or_else(|e| Err(Error::DebugProbeError(e)))?;
+        // Print stats.
+        println!("Wrote {:?} bytes in {:?} chunks in {:?}", bytes, chunks.len(), elapsed);
+        print_statistics(&st_link.statistics());
+
+        if options.verify {
+            let mismatches = verify_chunks(st_link, &chunks)?;
+            if !mismatches.is_empty() {
+                // Only print the first few mismatches, a failed write usually produces a lot of them.
+                for mismatch in mismatches.iter().take(16) {
+                    println!(
+                        "Mismatch at 0x{:08x}: expected 0x{:02x}, read 0x{:02x}",
+                        mismatch.address, mismatch.expected, mismatch.actual
+                    );
+                }
+                println!("{:?} of {:?} bytes differ.", mismatches.len(), bytes);
+                return Err(Error::Custom("Verification of the written memory failed."));
+            }
+            println!("Verified {:?} bytes.", bytes);
+        }
 
-//     Ok(())
-// }
+        Ok(())
+    })
+}
 
 fn reset_target_of_device(n: u8, assert: Option<bool>) -> Result<(), Error> {
     with_device(n, |st_link| {
@@ -387,7 +502,7 @@ fn trace_u32_on_target(n: u8, loc: u32) -> Result<(), Error> {
             let instant = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
 
             // Read data.
-            let value: u32 = st_link.read(loc)
+            let value: u32 = st_link.read_at(loc)
                                     .or_local_err()?;
 
             xs.push(instant);
@@ -440,3 +555,64 @@ where
     
     f(&mut st_link)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ihex::Chunk, load_image, offset_chunks, patch_lpc_checksum, Error};
+    use std::path::Path;
+
+    #[test]
+    fn load_image_skip_without_base_should_error() {
+        // The error has to come before the file is read, so a missing file does not matter.
+        match load_image(Path::new("missing.hex"), None, 4) {
+            Err(Error::Custom(_)) => (),
+            _ => panic!("Expected skipping bytes of an Intel HEX file to be rejected"),
+        }
+    }
+
+    #[test]
+    fn offset_chunks_moves_all_chunks() {
+        let mut chunks = vec![
+            Chunk { address: 0x0000_0000, data: vec![0; 4] },
+            Chunk { address: 0x0000_0100, data: vec![0; 4] },
+        ];
+        debug_assert!(offset_chunks(&mut chunks, 0x1000).is_ok());
+        debug_assert_eq!(chunks[0].address, 0x0000_1000);
+        debug_assert_eq!(chunks[1].address, 0x0000_1100);
+    }
+
+    #[test]
+    fn offset_chunks_up_to_4gb() {
+        let mut chunks = vec![Chunk { address: 0xFFFF_FE00, data: vec![0; 0x100] }];
+        debug_assert!(offset_chunks(&mut chunks, 0x100).is_ok());
+        debug_assert_eq!(chunks[0].address, 0xFFFF_FF00);
+    }
+
+    #[test]
+    fn offset_chunks_past_4gb_should_error() {
+        let mut chunks = vec![Chunk { address: 0xFFFF_FF00, data: vec![0; 0x100] }];
+        debug_assert!(offset_chunks(&mut chunks, 0x1).is_err());
+        let mut chunks = vec![Chunk { address: 0xFFFF_FF00, data: vec![0; 0x4] }];
+        debug_assert!(offset_chunks(&mut chunks, 0x100).is_err());
+    }
+
+    #[test]
+    fn patch_lpc_checksum_of_vector_table() {
+        // Initial SP, reset, NMI and hard fault handlers of an LPC11xx image, the rest is reserved.
+        let words = [0x1000_2000u32, 0x0000_00D5, 0x0000_00DD, 0x0000_00DF, 0, 0, 0, 0];
+        let data = words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+        let mut chunks = vec![
+            Chunk { address: 0x0000_1000, data: vec![0; 4] },
+            Chunk { address: 0x0000_0000, data },
+        ];
+        debug_assert!(patch_lpc_checksum(&mut chunks).is_ok());
+        debug_assert_eq!(chunks[1].data[28..32], 0xEFFF_DD6Fu32.to_le_bytes());
+        debug_assert_eq!(chunks[0].data, vec![0; 4]);
+    }
+
+    #[test]
+    fn patch_lpc_checksum_without_vector_table_should_error() {
+        let mut chunks = vec![Chunk { address: 0x0000_0000, data: vec![0; 16] }];
+        debug_assert!(patch_lpc_checksum(&mut chunks).is_err());
+    }
+}
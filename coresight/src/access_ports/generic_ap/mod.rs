@@ -8,7 +8,7 @@ use crate::common::Register;
 use crate::access_ports::APRegister;
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Primitive, Clone, Copy)]
+#[derive(Debug, Primitive, Clone, Copy, PartialEq)]
 pub enum APClass {
     Undefined = 0b0000,
     COMAP = 0b0001,
@@ -27,7 +27,9 @@ pub enum APType {
     AMBA_APB2_APB3 = 0x2,
     AMBA_AXI3_AXI4 = 0x4,
     AMBA_AHB5 = 0x5,
-    AMBA_AHB4 = 0x6,
+    AMBA_APB4_APB5 = 0x6,
+    AMBA_AXI5 = 0x7,
+    AMBA_AHB5_HPROT = 0x8,
 }
 
 impl Default for APType {
@@ -42,7 +44,7 @@ define_ap_register!(GenericAP, IDR, 0x0FC, [
         (CLASS: APClass),
         (_RES0: u8),
         (VARIANT: u8),
-        (TYPE: APType),
+        (TYPE: Option<APType>),
     ],
     value,
     IDR {
@@ -51,13 +53,14 @@ define_ap_register!(GenericAP, IDR, 0x0FC, [
         CLASS:    APClass::from_u8(((value >> 13) & 0x0F) as u8).unwrap(),
         _RES0:     0,
         VARIANT:  ((value >> 4) & 0x0F) as u8,
-        TYPE:     APType::from_u8((value & 0x0F) as u8).unwrap()
+        // Reserved types decode to `None` instead of panicking.
+        TYPE:     APType::from_u8((value & 0x0F) as u8)
     },
       (u32::from(value.REVISION       ) << 28)
     | (u32::from(value.DESIGNER       ) << 17)
     | (value.CLASS.to_u32().unwrap()    << 13)
     | (u32::from(value.VARIANT        ) <<  4)
-    | (value.TYPE.to_u32().unwrap()          )
+    | (value.TYPE.map_or(0, |t| t.to_u32().unwrap()))
 );
 
 define_ap_register!(GenericAP, BASE, 0x0F8, [
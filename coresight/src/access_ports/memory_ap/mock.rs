@@ -6,12 +6,22 @@ use super::{
     CSW,
     DataSize,
     TAR,
+    TAR2,
     DRW,
+    CFG,
+};
+use crate::access_ports::generic_ap::{
+    GenericAP,
+    IDR,
 };
 use crate::common::Register;
 
 pub struct MockMemoryAP {
     pub data: Vec<u8>,
+    /// The address and size in bytes of every DRW access, in order.
+    pub transfers: Vec<(u32, u8)>,
+    /// Every value written to the TAR, in order.
+    pub tar_writes: Vec<u32>,
     store: HashMap<(u8, u8), u32>,
 }
 
@@ -26,12 +36,43 @@ impl MockMemoryAP {
         let mut store = HashMap::new();
         store.insert((CSW::ADDRESS, CSW::APBANKSEL), 0);
         store.insert((TAR::ADDRESS, TAR::APBANKSEL), 0);
+        store.insert((TAR2::ADDRESS, TAR2::APBANKSEL), 0);
+        store.insert((CFG::ADDRESS, CFG::APBANKSEL), 0);
         store.insert((DRW::ADDRESS, DRW::APBANKSEL), 0);
+        // An AHB-AP designed by ARM.
+        store.insert((IDR::ADDRESS, IDR::APBANKSEL), 0x2477_0011);
         Self {
-            data: vec![0; 256],
+            data: vec![0; 2048],
+            transfers: vec![],
+            tar_writes: vec![],
             store,
         }
     }
+
+    /// Sets the raw value of the IDR, e.g. to mock an AP with a reserved type that `IDR` cannot hold.
+    pub fn set_idr(&mut self, value: u32) {
+        self.store.insert((IDR::ADDRESS, IDR::APBANKSEL), value);
+    }
+
+    /// Records a successful DRW access and advances the TAR afterwards if auto increment is enabled in the CSW.
+    ///
+    /// Like on real hardware, the TAR only increments within a 1KB block and wraps around at its end.
+    fn auto_increment<T>(&mut self, result: Result<T, MockMemoryError>) -> Result<T, MockMemoryError> {
+        let value = result?;
+        let csw = CSW::from(self.store[&(CSW::ADDRESS, CSW::APBANKSEL)]);
+        let size = match csw.SIZE {
+            DataSize::U8 => 1,
+            DataSize::U16 => 2,
+            _ => 4,
+        };
+        let address = self.store[&(TAR::ADDRESS, TAR::APBANKSEL)];
+        self.transfers.push((address, size as u8));
+        if csw.AddrInc == 1 {
+            let address = (address & !0x3FF) | (address.wrapping_add(size) & 0x3FF);
+            self.store.insert((TAR::ADDRESS, TAR::APBANKSEL), address);
+        }
+        Ok(value)
+    }
 }
 
 impl<REGISTER> APAccess<MemoryAP, REGISTER> for MockMemoryAP
@@ -42,35 +83,42 @@ where
 
     /// Mocks the read_register method of a AP.
     /// 
+    /// 8 and 16 bit reads return the data on the byte lanes selected by the address, like a real MEM-AP does.
     /// Returns an Error if any bad instructions or values are chosen.
     fn read_register_ap(&mut self, _port: MemoryAP, _register: REGISTER) -> Result<REGISTER, Self::Error> {
         let csw = self.store[&(CSW::ADDRESS, CSW::APBANKSEL)];
         let address = self.store[&(TAR::ADDRESS, TAR::APBANKSEL)];
         match (REGISTER::ADDRESS, REGISTER::APBANKSEL) {
-            (DRW::ADDRESS, DRW::APBANKSEL) => match CSW::from(csw).SIZE {
-                DataSize::U32 => Ok(REGISTER::from(
-                     u32::from(self.data[address as usize    ])        |
-                    (u32::from(self.data[address as usize + 1]) <<  8) |
-                    (u32::from(self.data[address as usize + 2]) << 16) |
-                    (u32::from(self.data[address as usize + 3]) << 24)
-                )),
-                DataSize::U16 => Ok(REGISTER::from(
-                     u32::from(self.data[address as usize    ])         |
-                    (u32::from(self.data[address as usize + 1]) <<  8)
-                )),
-                DataSize::U8 => Ok(REGISTER::from(
-                     u32::from(self.data[address as usize    ])
-                )),
-                _ => Err(MockMemoryError::UnknownWidth)
+            (DRW::ADDRESS, DRW::APBANKSEL) => {
+                let result = match CSW::from(csw).SIZE {
+                    DataSize::U32 => Ok(REGISTER::from(
+                         u32::from(self.data[address as usize    ])        |
+                        (u32::from(self.data[address as usize + 1]) <<  8) |
+                        (u32::from(self.data[address as usize + 2]) << 16) |
+                        (u32::from(self.data[address as usize + 3]) << 24)
+                    )),
+                    DataSize::U16 => Ok(REGISTER::from((
+                         u32::from(self.data[address as usize    ])         |
+                        (u32::from(self.data[address as usize + 1]) <<  8)
+                    ) << ((address & 0x2) * 8))),
+                    DataSize::U8 => Ok(REGISTER::from(
+                         u32::from(self.data[address as usize    ]) << ((address & 0x3) * 8)
+                    )),
+                    _ => Err(MockMemoryError::UnknownWidth)
+                };
+                self.auto_increment(result)
             },
             (CSW::ADDRESS, CSW::APBANKSEL) => Ok(REGISTER::from(self.store[&(REGISTER::ADDRESS, REGISTER::APBANKSEL)])),
             (TAR::ADDRESS, TAR::APBANKSEL) => Ok(REGISTER::from(self.store[&(REGISTER::ADDRESS, REGISTER::APBANKSEL)])),
+            (TAR2::ADDRESS, TAR2::APBANKSEL) => Ok(REGISTER::from(self.store[&(REGISTER::ADDRESS, REGISTER::APBANKSEL)])),
+            (CFG::ADDRESS, CFG::APBANKSEL) => Ok(REGISTER::from(self.store[&(REGISTER::ADDRESS, REGISTER::APBANKSEL)])),
             _ => Err(MockMemoryError::UnknownRegister)
         }
     }
 
     /// Mocks the write_register method of a AP.
     /// 
+    /// 8 and 16 bit writes take the data from the byte lanes selected by the address, like a real MEM-AP does.
     /// Returns an Error if any bad instructions or values are chosen.
     fn write_register_ap(&mut self, _port: MemoryAP, register: REGISTER) -> Result<(), Self::Error> {
         let value = register.into();
@@ -78,28 +126,68 @@ where
         let csw = self.store[&(CSW::ADDRESS, CSW::APBANKSEL)];
         let address = self.store[&(TAR::ADDRESS, TAR::APBANKSEL)];
         match (REGISTER::ADDRESS, REGISTER::APBANKSEL) {
-            (DRW::ADDRESS, DRW::APBANKSEL) => match CSW::from(csw).SIZE {
-                DataSize::U32 => {
-                    self.data[address as usize    ] =  value        as u8;
-                    self.data[address as usize + 1] = (value >>  8) as u8;
-                    self.data[address as usize + 2] = (value >> 16) as u8;
-                    self.data[address as usize + 3] = (value >> 24) as u8;
-                    Ok(())
-                },
-                DataSize::U16 => {
-                    self.data[address as usize    ] =  value        as u8;
-                    self.data[address as usize + 1] = (value >>  8) as u8;
-                    Ok(())
-                },
-                DataSize::U8 => {
-                    self.data[address as usize    ] =  value        as u8;
-                    Ok(())
-                },
-                _ => Err(MockMemoryError::UnknownWidth)
+            (DRW::ADDRESS, DRW::APBANKSEL) => {
+                let result = match CSW::from(csw).SIZE {
+                    DataSize::U32 => {
+                        self.data[address as usize    ] =  value        as u8;
+                        self.data[address as usize + 1] = (value >>  8) as u8;
+                        self.data[address as usize + 2] = (value >> 16) as u8;
+                        self.data[address as usize + 3] = (value >> 24) as u8;
+                        Ok(())
+                    },
+                    DataSize::U16 => {
+                        let value = value >> ((address & 0x2) * 8);
+                        self.data[address as usize    ] =  value        as u8;
+                        self.data[address as usize + 1] = (value >>  8) as u8;
+                        Ok(())
+                    },
+                    DataSize::U8 => {
+                        let value = value >> ((address & 0x3) * 8);
+                        self.data[address as usize    ] =  value        as u8;
+                        Ok(())
+                    },
+                    _ => Err(MockMemoryError::UnknownWidth)
+                };
+                self.auto_increment(result)
             },
             (CSW::ADDRESS, CSW::APBANKSEL) => Ok(()),
-            (TAR::ADDRESS, TAR::APBANKSEL) => Ok(()),
+            (TAR::ADDRESS, TAR::APBANKSEL) => {
+                self.tar_writes.push(value);
+                Ok(())
+            },
+            (TAR2::ADDRESS, TAR2::APBANKSEL) => Ok(()),
+            (CFG::ADDRESS, CFG::APBANKSEL) => Ok(()),
             _ => Err(MockMemoryError::UnknownRegister)
         }
     }
-}
\ No newline at end of file
+}
+
+impl<REGISTER> APAccess<GenericAP, REGISTER> for MockMemoryAP
+where
+    REGISTER: APRegister<GenericAP>
+{
+    type Error = MockMemoryError;
+
+    /// Mocks the read_register method of a AP.
+    ///
+    /// Only the IDR is supported, which reads as an AHB-AP unless it was written before.
+    fn read_register_ap(&mut self, _port: GenericAP, _register: REGISTER) -> Result<REGISTER, Self::Error> {
+        match (REGISTER::ADDRESS, REGISTER::APBANKSEL) {
+            (IDR::ADDRESS, IDR::APBANKSEL) => Ok(REGISTER::from(self.store[&(REGISTER::ADDRESS, REGISTER::APBANKSEL)])),
+            _ => Err(MockMemoryError::UnknownRegister)
+        }
+    }
+
+    /// Mocks the write_register method of a AP.
+    ///
+    /// Writing the IDR, which is read-only on real hardware, changes the kind of AP that is mocked.
+    fn write_register_ap(&mut self, _port: GenericAP, register: REGISTER) -> Result<(), Self::Error> {
+        match (REGISTER::ADDRESS, REGISTER::APBANKSEL) {
+            (IDR::ADDRESS, IDR::APBANKSEL) => {
+                self.store.insert((REGISTER::ADDRESS, REGISTER::APBANKSEL), register.into());
+                Ok(())
+            },
+            _ => Err(MockMemoryError::UnknownRegister)
+        }
+    }
+}
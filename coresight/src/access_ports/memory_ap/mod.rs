@@ -82,6 +82,16 @@ define_ap_register!(MemoryAP, TAR, 0x04, [
     value.address
 );
 
+define_ap_register!(MemoryAP, TAR2, 0x08, [
+        (address: u32),
+    ],
+    value,
+    TAR2 {
+        address: value
+    },
+    value.address
+);
+
 define_ap_register!(MemoryAP, DRW, 0x0C, [
         (data: u32),
     ],
@@ -90,4 +100,20 @@ define_ap_register!(MemoryAP, DRW, 0x0C, [
         data: value
     },
     value.data
-);
\ No newline at end of file
+);
+
+define_ap_register!(MemoryAP, CFG, 0xF4, [
+        (LD: u8), // 1 bit
+        (LA: u8), // 1 bit
+        (BE: u8), // 1 bit
+    ],
+    value,
+    CFG {
+        LD: ((value >> 2) & 0x01) as u8,
+        LA: ((value >> 1) & 0x01) as u8,
+        BE: (value & 0x01) as u8,
+    },
+      (u32::from(value.LD) << 2)
+    | (u32::from(value.LA) << 1)
+    | u32::from(value.BE)
+);
@@ -11,6 +11,10 @@ pub enum AccessPortError {
     ProbeError,
     InvalidAccessPortNumber,
     MemoryNotAligned,
+    TransferSizeNotSupported,
+    AddressOutOfRange,
+    NotAMemoryAP,
+    UnknownAPType,
 }
 
 pub trait APRegister<PORT: AccessPort>: Register + Sized {
@@ -0,0 +1,83 @@
+use crate::component::JEP106Code;
+
+/// The identity of a chip or debug port as reported by the DP.
+///
+/// Both IDCODE (DPIDR) and TARGETID encode the designer as a JEP106 code in bits 11:1 and have bit 0 set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChipInfo {
+    /// The JEP106 code of the designer.
+    pub designer: JEP106Code,
+    pub part_number: u16,
+    pub revision: u8,
+}
+
+impl ChipInfo {
+    /// Decodes the TARGETID register of a DPv2 debug port, which identifies the chip itself.
+    ///
+    /// Returns `None` if bit 0 is not set, which means the register does not hold a valid identity.
+    pub fn from_target_id(value: u32) -> Option<Self> {
+        Self::decode(value, ((value >> 12) & 0xFFFF) as u16)
+    }
+
+    /// Decodes the IDCODE (DPIDR) register, which identifies the debug port implementation.
+    ///
+    /// Returns `None` if bit 0 is not set, which means the register does not hold a valid identity.
+    pub fn from_idcode(value: u32) -> Option<Self> {
+        Self::decode(value, ((value >> 20) & 0xFF) as u16)
+    }
+
+    fn decode(value: u32, part_number: u16) -> Option<Self> {
+        if value & 0x1 == 0 {
+            return None;
+        }
+        // Bits 11:8 hold the continuation code, bits 7:1 the identity code.
+        Some(ChipInfo {
+            designer: JEP106Code::new(((value >> 8) & 0x0F) as u8, ((value >> 1) & 0x7F) as u8),
+            part_number,
+            revision: (value >> 28) as u8,
+        })
+    }
+
+    /// Returns the name of the designer, if it is known.
+    pub fn designer_name(&self) -> Option<&'static str> {
+        self.designer.get()
+    }
+}
+
+impl std::fmt::Display for ChipInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} device, Part Number = {:04X}, Revision = {}",
+            self.designer_name().unwrap_or("Unknown designer"),
+            self.part_number,
+            self.revision
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChipInfo;
+
+    #[test]
+    fn decode_stm32_target_id() {
+        let info = ChipInfo::from_target_id(0x1041_5041).unwrap();
+        debug_assert_eq!(info.part_number, 0x0415);
+        debug_assert_eq!(info.revision, 1);
+        debug_assert_eq!(info.designer_name(), Some("STMicroelectronics"));
+    }
+
+    #[test]
+    fn decode_arm_idcode() {
+        let info = ChipInfo::from_idcode(0x2BA0_1477).unwrap();
+        debug_assert_eq!(info.part_number, 0xBA);
+        debug_assert_eq!(info.revision, 2);
+        debug_assert_eq!(info.designer_name(), Some("ARM Ltd"));
+    }
+
+    #[test]
+    fn invalid_identity_should_be_none() {
+        debug_assert!(ChipInfo::from_target_id(0x1041_5040).is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use enum_primitive_derive::Primitive;
+use num_traits::cast::FromPrimitive;
+pub use jep106::JEP106Code;
+
+/// Offset of the PIDR4 register inside the 4KB block of a component.
+/// The identification registers PIDR4-PIDR7, PIDR0-PIDR3 and CIDR0-CIDR3 follow in this order.
+pub const ID_REGISTERS_OFFSET: u32 = 0xFD0;
+
+/// The number of 32 bit identification registers starting at `ID_REGISTERS_OFFSET`.
+pub const ID_REGISTERS_COUNT: usize = 12;
+
+/// The component class as encoded in CIDR1.
+#[derive(Debug, Primitive, Clone, Copy, PartialEq)]
+pub enum ComponentClass {
+    GenericVerification = 0x0,
+    RomTable = 0x1,
+    CoreSight = 0x9,
+    PeripheralTestBlock = 0xB,
+    /// A component of an OptimoDE Data Engine SubSystem.
+    OptimoDE = 0xD,
+    GenericIP = 0xE,
+    PrimeCell = 0xF,
+}
+
+#[derive(Debug)]
+pub enum ComponentError {
+    /// The CIDR registers do not contain the fixed preamble, so there is no component at this address.
+    InvalidPreamble,
+    /// The CIDR registers contain a component class that is reserved.
+    UnknownClass(u8),
+}
+
+/// The identification of a CoreSight component as read from its CIDR and PIDR registers.
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentId {
+    /// The address of the 4KB block containing the identification registers.
+    pub base_address: u32,
+    pub class: ComponentClass,
+    /// The JEP106 code of the designer of the component.
+    /// Only valid if `uses_jep106` is set, older components used ASCII codes instead.
+    pub designer: JEP106Code,
+    pub uses_jep106: bool,
+    /// The 12 bit part number assigned by the designer.
+    pub part_number: u16,
+    pub revision: u8,
+    /// The customer modification number, non-zero if the component was modified by the integrator.
+    pub customer_modified: u8,
+    /// The manufacturing revision of the component.
+    pub revision_and: u8,
+    /// The size of the component in 4KB blocks, expressed as log2.
+    pub size_log2_4kb: u8,
+}
+
+impl ComponentId {
+    /// Decodes the identification registers of a component located at `base_address`.
+    ///
+    /// `registers` holds the values of PIDR4-PIDR7, PIDR0-PIDR3 and CIDR0-CIDR3
+    /// as they are laid out in memory starting at `ID_REGISTERS_OFFSET`.
+    pub fn from_id_registers(base_address: u32, registers: &[u32; ID_REGISTERS_COUNT]) -> Result<Self, ComponentError> {
+        // Only the lowest byte of each identification register is valid.
+        let byte = |index: usize| (registers[index] & 0xFF) as u8;
+        let pidr = [byte(4), byte(5), byte(6), byte(7), byte(0), byte(1), byte(2), byte(3)];
+        let cidr = [byte(8), byte(9), byte(10), byte(11)];
+
+        if cidr[0] != 0x0D || (cidr[1] & 0x0F) != 0x00 || cidr[2] != 0x05 || cidr[3] != 0xB1 {
+            return Err(ComponentError::InvalidPreamble);
+        }
+
+        let class = cidr[1] >> 4;
+        let class = ComponentClass::from_u8(class).ok_or(ComponentError::UnknownClass(class))?;
+
+        Ok(ComponentId {
+            base_address,
+            class,
+            designer: JEP106Code::new(pidr[4] & 0x0F, ((pidr[2] & 0x07) << 4) | (pidr[1] >> 4)),
+            uses_jep106: (pidr[2] & 0x08) != 0,
+            part_number: u16::from(pidr[0]) | (u16::from(pidr[1] & 0x0F) << 8),
+            revision: pidr[2] >> 4,
+            customer_modified: pidr[3] & 0x0F,
+            revision_and: pidr[3] >> 4,
+            size_log2_4kb: pidr[4] >> 4,
+        })
+    }
+
+    /// Returns the name of the designer of the component, if it is known.
+    pub fn designer_name(&self) -> Option<&'static str> {
+        if self.uses_jep106 {
+            self.designer.get()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentClass, ComponentId};
+
+    #[test]
+    fn decode_cortex_m4_rom_table() {
+        // PIDR4-7, PIDR0-3, CIDR0-3 of the Cortex-M4 ROM table.
+        let registers = [
+            0x04, 0x00, 0x00, 0x00,
+            0xC4, 0xB4, 0x0B, 0x00,
+            0x0D, 0x10, 0x05, 0xB1,
+        ];
+        let id = ComponentId::from_id_registers(0xE00FF000, &registers).unwrap();
+        debug_assert_eq!(id.class, ComponentClass::RomTable);
+        debug_assert_eq!(id.part_number, 0x4C4);
+        debug_assert_eq!(id.revision, 0);
+        debug_assert_eq!(id.designer_name(), Some("ARM Ltd"));
+    }
+
+    #[test]
+    fn decode_invalid_preamble() {
+        let registers = [0; 12];
+        debug_assert!(ComponentId::from_id_registers(0x0, &registers).is_err());
+    }
+}
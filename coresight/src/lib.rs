@@ -2,3 +2,5 @@ pub mod dap_access;
 pub mod ap_access;
 pub mod access_ports;
 pub mod common;
+pub mod component;
+pub mod chip_info;
@@ -1,4 +1,7 @@
 pub mod memory_interface;
+pub mod romtable;
+#[cfg(test)]
+mod mock;
 
 use coresight::access_ports::AccessPortError;
 
@@ -43,7 +46,7 @@ pub trait MI {
     /// 
     /// The address where the read should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    fn read<S: ToMemoryReadSize>(&mut self, address: u32) -> Result<S, AccessPortError>;
+    fn read<S: ToMemoryReadSize>(&mut self, address: u64) -> Result<S, AccessPortError>;
 
     /// Read a block of words of the size defined by S at `addr`.
     /// 
@@ -52,7 +55,7 @@ pub trait MI {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn read_block<S: ToMemoryReadSize>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &mut [S]
     ) -> Result<(), AccessPortError>;
 
@@ -62,14 +65,186 @@ pub trait MI {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn write<S: ToMemoryReadSize>(
         &mut self,
-        addr: u32,
+        addr: u64,
         data: S
     ) -> Result<(), AccessPortError>;
 
     /// Like `write_block` but with much simpler stucture but way lower performance for u8 and u16.
     fn write_block<S: ToMemoryReadSize>(
         &mut self,
-        addr: u32,
+        addr: u64,
         data: &[S]
     ) -> Result<(), AccessPortError>;
+
+    /// Read a block of bytes at `address`.
+    ///
+    /// There are no alignment requirements. Implementations should use the widest transfers possible,
+    /// e.g. `ADIMemoryInterface` only reads the unaligned bytes at the start and the end with 8 bit accesses.
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), AccessPortError> {
+        self.read_block(address, data)
+    }
+
+    /// Write a block of bytes at `address`.
+    ///
+    /// There are no alignment requirements. Implementations should use the widest transfers possible,
+    /// e.g. `ADIMemoryInterface` only writes the unaligned bytes at the start and the end with 8 bit accesses.
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), AccessPortError> {
+        self.write_block(address, data)
+    }
+
+    /// Read a 32 bit word at `address` using a single 32 bit access.
+    fn read_word_32(&mut self, address: u64) -> Result<u32, AccessPortError> {
+        self.read(address)
+    }
+
+    /// Read a 16 bit word at `address` using a single 16 bit access.
+    ///
+    /// Use this for peripheral registers that must not be accessed with a wider transfer size.
+    fn read_word_16(&mut self, address: u64) -> Result<u16, AccessPortError> {
+        self.read(address)
+    }
+
+    /// Read an 8 bit word at `address` using a single 8 bit access.
+    ///
+    /// Use this for peripheral registers that must not be accessed with a wider transfer size.
+    fn read_word_8(&mut self, address: u64) -> Result<u8, AccessPortError> {
+        self.read(address)
+    }
+
+    /// Read a block of 16 bit words at `address` using one 16 bit access per word.
+    ///
+    /// Unlike `read_block`, no wider transfers are used, so this is safe for 16 bit only peripherals.
+    fn read_block_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), AccessPortError> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_16(address + offset as u64 * 2)?;
+        }
+        Ok(())
+    }
+
+    /// Write a 32 bit word at `address` using a single 32 bit access.
+    fn write_word_32(&mut self, address: u64, data: u32) -> Result<(), AccessPortError> {
+        self.write(address, data)
+    }
+
+    /// Read the 32 bit word at `address`, pass it to `f` and write back the returned value.
+    ///
+    /// Exactly one 32 bit read and one 32 bit write are performed.
+    /// Nothing is written if the read fails.
+    fn modify_word_32<F: FnOnce(u32) -> u32>(&mut self, address: u64, f: F) -> Result<(), AccessPortError> {
+        let value = self.read_word_32(address)?;
+        self.write_word_32(address, f(value))
+    }
+
+    /// Set all bits of `mask` in the 32 bit word at `address`, leaving the other bits untouched.
+    fn set_bits_32(&mut self, address: u64, mask: u32) -> Result<(), AccessPortError> {
+        self.modify_word_32(address, |value| value | mask)
+    }
+
+    /// Clear all bits of `mask` in the 32 bit word at `address`, leaving the other bits untouched.
+    fn clear_bits_32(&mut self, address: u64, mask: u32) -> Result<(), AccessPortError> {
+        self.modify_word_32(address, |value| value & !mask)
+    }
+
+    /// Write a 16 bit word at `address` using a single 16 bit access.
+    ///
+    /// The neighbouring bytes of the containing 32 bit word are left untouched.
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), AccessPortError> {
+        self.write(address, data)
+    }
+
+    /// Write an 8 bit word at `address` using a single 8 bit access.
+    ///
+    /// The neighbouring bytes of the containing 32 bit word are left untouched.
+    fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), AccessPortError> {
+        self.write(address, data)
+    }
+
+    /// Write a block of 16 bit words at `address` using one 16 bit access per word.
+    ///
+    /// Unlike `write_block`, no wider transfers are used, so this is safe for 16 bit only peripherals.
+    fn write_block_16(&mut self, address: u64, data: &[u16]) -> Result<(), AccessPortError> {
+        for (offset, word) in data.iter().enumerate() {
+            self.write_word_16(address + offset as u64 * 2, *word)?;
+        }
+        Ok(())
+    }
+}
+
+/// Memory access with 32 bit addresses, for targets whose address space fits into 32 bits.
+///
+/// This is implemented for every `MI`, so callers don't have to widen each address to `u64`.
+pub trait MI32 {
+    /// Like `MI::read`, but with a 32 bit address.
+    fn read_at<S: ToMemoryReadSize>(&mut self, address: u32) -> Result<S, AccessPortError>;
+
+    /// Like `MI::read_block`, but with a 32 bit address.
+    fn read_block_at<S: ToMemoryReadSize>(&mut self, address: u32, data: &mut [S]) -> Result<(), AccessPortError>;
+
+    /// Like `MI::write`, but with a 32 bit address.
+    fn write_at<S: ToMemoryReadSize>(&mut self, address: u32, data: S) -> Result<(), AccessPortError>;
+
+    /// Like `MI::write_block`, but with a 32 bit address.
+    fn write_block_at<S: ToMemoryReadSize>(&mut self, address: u32, data: &[S]) -> Result<(), AccessPortError>;
+}
+
+impl<M: MI> MI32 for M {
+    fn read_at<S: ToMemoryReadSize>(&mut self, address: u32) -> Result<S, AccessPortError> {
+        self.read(u64::from(address))
+    }
+
+    fn read_block_at<S: ToMemoryReadSize>(&mut self, address: u32, data: &mut [S]) -> Result<(), AccessPortError> {
+        self.read_block(u64::from(address), data)
+    }
+
+    fn write_at<S: ToMemoryReadSize>(&mut self, address: u32, data: S) -> Result<(), AccessPortError> {
+        self.write(u64::from(address), data)
+    }
+
+    fn write_block_at<S: ToMemoryReadSize>(&mut self, address: u32, data: &[S]) -> Result<(), AccessPortError> {
+        self.write_block(u64::from(address), data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MI;
+    use crate::mock::MockMemory;
+
+    #[test]
+    fn read_block_16_uses_16_bit_accesses() {
+        let mut memory = MockMemory::default();
+        memory.insert_word(0x4000_0000, 0x2222_1111);
+        memory.insert_word(0x4000_0004, 0x4444_3333);
+        let mut data = [0u16; 3];
+        debug_assert!(memory.read_block_16(0x4000_0002, &mut data).is_ok());
+        debug_assert_eq!(data, [0x2222, 0x3333, 0x4444]);
+        debug_assert_eq!(memory.accesses, vec![(0x4000_0002, 2), (0x4000_0004, 2), (0x4000_0006, 2)]);
+    }
+
+    #[test]
+    fn write_block_16_uses_16_bit_accesses() {
+        let mut memory = MockMemory::default();
+        debug_assert!(memory.write_block_16(0x4000_0000, &[0x1234, 0x5678]).is_ok());
+        debug_assert_eq!(memory.accesses, vec![(0x4000_0000, 2), (0x4000_0002, 2)]);
+        debug_assert_eq!(memory.word(0x4000_0000), 0x5678_1234);
+    }
+
+    #[test]
+    fn modify_word_32_reads_and_writes_once() {
+        let mut memory = MockMemory::default();
+        memory.insert_word(0xE004_2004, 0xE004_2004);
+        debug_assert!(memory.modify_word_32(0xE004_2004, |value| value ^ 0xFFFF_0000).is_ok());
+        debug_assert_eq!(memory.accesses, vec![(0xE004_2004, 4), (0xE004_2004, 4)]);
+        debug_assert_eq!(memory.word(0xE004_2004), 0x1FFB_2004);
+    }
+
+    #[test]
+    fn set_and_clear_bits_32() {
+        let mut memory = MockMemory::default();
+        memory.insert_word(0x4002_1018, 0x4002_1018);
+        debug_assert!(memory.set_bits_32(0x4002_1018, 0x0000_0003).is_ok());
+        debug_assert_eq!(memory.word(0x4002_1018), 0x4002_101B);
+        debug_assert!(memory.clear_bits_32(0x4002_1018, 0x0000_1018).is_ok());
+        debug_assert_eq!(memory.word(0x4002_1018), 0x4002_0003);
+    }
 }
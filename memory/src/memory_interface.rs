@@ -1,21 +1,67 @@
-use crate::ToMemoryReadSize;
+use crate::{MI, ToMemoryReadSize};
 
 use coresight::access_ports::{
     APRegister,
+    generic_ap::{
+        GenericAP,
+        APClass,
+        APType,
+        IDR,
+    },
     memory_ap::{
         MemoryAP,
         DataSize,
         CSW,
         TAR,
+        TAR2,
         DRW,
+        CFG,
     },
     AccessPortError,
 };
 use coresight::ap_access::APAccess;
 
 /// A struct to give access to a targets memory using a certain DAP.
+#[derive(Clone, Copy)]
 pub struct ADIMemoryInterface {
     access_port: MemoryAP,
+    /// The bus the MEM-AP is connected to, as reported in its IDR.
+    ap_type: APType,
+    /// The AxPROT attributes used for transfers on an AXI-AP.
+    axi_prot: u8,
+    /// The AxCACHE attributes used for transfers on an AXI-AP.
+    axi_cache: u8,
+    /// Whether the MEM-AP supports addresses wider than 32 bits through TAR2.
+    large_address: bool,
+}
+
+/// An `ADIMemoryInterface` together with the debug port it is accessed through.
+///
+/// This makes the memory behind any MEM-AP usable wherever an `MI` is expected.
+pub struct ConnectedMemoryInterface<'a, AP> {
+    interface: ADIMemoryInterface,
+    debug_port: &'a mut AP,
+}
+
+impl<'a, AP> MI for ConnectedMemoryInterface<'a, AP>
+where
+    AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
+{
+    fn read<S: ToMemoryReadSize>(&mut self, address: u64) -> Result<S, AccessPortError> {
+        self.interface.read(self.debug_port, address)
+    }
+
+    fn read_block<S: ToMemoryReadSize>(&mut self, address: u64, data: &mut [S]) -> Result<(), AccessPortError> {
+        self.interface.read_block(self.debug_port, address, data)
+    }
+
+    fn write<S: ToMemoryReadSize>(&mut self, addr: u64, data: S) -> Result<(), AccessPortError> {
+        self.interface.write(self.debug_port, addr, data)
+    }
+
+    fn write_block<S: ToMemoryReadSize>(&mut self, addr: u64, data: &[S]) -> Result<(), AccessPortError> {
+        self.interface.write_block(self.debug_port, addr, data)
+    }
 }
 
 pub fn bytes_to_transfer_size(bytes: u8) -> DataSize {
@@ -36,14 +82,131 @@ pub fn bytes_to_transfer_size(bytes: u8) -> DataSize {
     }
 }
 
+/// The TAR is only guaranteed to auto increment within the lower 10 bits of the address.
+/// It has to be written again whenever a transfer crosses a 1KB boundary.
+const AUTO_INCREMENT_WRAP_MASK: u32 = 0x3FF;
+
+/// Returns the shift that moves a value to the byte lanes used for a transfer at `address`.
+///
+/// For 8 and 16 bit transfers the MEM-AP places the data on the byte lanes selected by the
+/// lower two address bits, both when reading from and writing to DRW.
+fn byte_lane_shift(address: u32) -> u32 {
+    (address & 0x3) * 8
+}
+
 impl ADIMemoryInterface {
     /// Creates a new MemoryInterface for given AccessPort.
+    ///
+    /// The AccessPort is assumed to be an AHB-AP, which is what Cortex-M based chips use.
     pub fn new(access_port_number: u8) -> Self {
+        Self::new_with_type(access_port_number, APType::AMBA_AHB3)
+    }
+
+    /// Creates a new MemoryInterface for given AccessPort which is connected to the bus given by `ap_type`.
+    ///
+    /// APB-APs only support 32 bit transfers, which is enforced for all accesses.
+    /// AXI-APs, including AXI5-APs, use non-secure, privileged data accesses unless configured otherwise with `set_axi_attributes`.
+    pub fn new_with_type(access_port_number: u8, ap_type: APType) -> Self {
         Self {
-            access_port: MemoryAP::new(access_port_number)
+            access_port: MemoryAP::new(access_port_number),
+            ap_type,
+            axi_prot: 0b011,
+            axi_cache: 0b0000,
+            large_address: false,
+        }
+    }
+
+    /// Creates a new MemoryInterface for given AccessPort, using the bus type reported in its IDR
+    /// and the large address support reported in its CFG.
+    ///
+    /// Returns `AccessPortError::NotAMemoryAP` if the AccessPort is not a MEM-AP
+    /// and `AccessPortError::UnknownAPType` if its bus type is reserved.
+    pub fn detect<AP>(debug_port: &mut AP, access_port_number: u8) -> Result<Self, AccessPortError>
+    where
+        AP: APAccess<GenericAP, IDR> + APAccess<MemoryAP, CFG>
+    {
+        let idr = debug_port.read_register_ap(GenericAP::new(access_port_number), IDR::default())
+                            .map_err(|_| AccessPortError::ProbeError)?;
+        if idr.CLASS != APClass::MEMAP {
+            return Err(AccessPortError::NotAMemoryAP);
+        }
+        let ap_type = idr.TYPE.ok_or(AccessPortError::UnknownAPType)?;
+        let mut interface = Self::new_with_type(access_port_number, ap_type);
+        interface.detect_large_address(debug_port)?;
+        Ok(interface)
+    }
+
+    /// Binds this interface to the debug port it is accessed through, so it can be used as an `MI`.
+    pub fn connect<AP>(self, debug_port: &mut AP) -> ConnectedMemoryInterface<'_, AP> {
+        ConnectedMemoryInterface {
+            interface: self,
+            debug_port,
         }
     }
 
+    /// Reads the CFG register of the AP to find out whether it supports addresses wider than 32 bits.
+    ///
+    /// Until this is called, all accesses above 4GB are rejected with `AccessPortError::AddressOutOfRange`.
+    pub fn detect_large_address<AP>(&mut self, debug_port: &mut AP) -> Result<bool, AccessPortError>
+    where
+        AP: APAccess<MemoryAP, CFG>
+    {
+        self.large_address = self.read_register_ap(debug_port, CFG::default())?.LA == 1;
+        Ok(self.large_address)
+    }
+
+    /// Sets the AxPROT and AxCACHE attributes used for transfers on an AXI-AP.
+    ///
+    /// `prot` holds the 3 AxPROT bits and `cache` the 4 AxCACHE bits as defined by the AXI specification.
+    /// The attributes are ignored for all other AP types.
+    pub fn set_axi_attributes(&mut self, prot: u8, cache: u8) {
+        self.axi_prot = prot & 0x07;
+        self.axi_cache = cache & 0x0F;
+    }
+
+    /// Builds the CSW value for transfers of the given size on this AP.
+    ///
+    /// Returns `AccessPortError::TransferSizeNotSupported` if the AP's bus cannot do transfers of that size.
+    fn build_csw(&self, size: DataSize) -> Result<CSW, AccessPortError> {
+        match (self.ap_type, size) {
+            (APType::AMBA_APB2_APB3, DataSize::U32)
+            | (APType::AMBA_APB4_APB5, DataSize::U32) => Ok(CSW { AddrInc: 1, SIZE: size, ..Default::default() }),
+            (APType::AMBA_APB2_APB3, _)
+            | (APType::AMBA_APB4_APB5, _) => Err(AccessPortError::TransferSizeNotSupported),
+            (APType::AMBA_AXI3_AXI4, _)
+            | (APType::AMBA_AXI5, _) => Ok(CSW {
+                PROT: self.axi_prot,
+                CACHE: self.axi_cache,
+                AddrInc: 1,
+                SIZE: size,
+                ..Default::default()
+            }),
+            _ => Ok(CSW { AddrInc: 1, SIZE: size, ..Default::default() }),
+        }
+    }
+
+    /// Prepares an access of `length` bytes at `address` and returns the lower 32 bits of the address for TAR.
+    ///
+    /// On APs with large address support the upper 32 bits are written to TAR2,
+    /// which is then used for the whole access.
+    /// Returns `AccessPortError::AddressOutOfRange` if the AP cannot reach all of the bytes with a single TAR2 value.
+    fn prepare_address<AP>(&self, debug_port: &mut AP, address: u64, length: usize) -> Result<u32, AccessPortError>
+    where
+        AP: APAccess<MemoryAP, TAR2>
+    {
+        let upper = (address >> 32) as u32;
+        let last = address
+            .checked_add((length as u64).saturating_sub(1))
+            .ok_or(AccessPortError::AddressOutOfRange)?;
+        if (last >> 32) as u32 != upper || (upper != 0 && !self.large_address) {
+            return Err(AccessPortError::AddressOutOfRange);
+        }
+        if self.large_address {
+            self.write_register_ap(debug_port, TAR2 { address: upper })?;
+        }
+        Ok(address as u32)
+    }
+
     /// Read a 32 bit register on the given AP.
     fn read_register_ap<REGISTER, AP>(&self, debug_port: &mut AP, register: REGISTER) -> Result<REGISTER, AccessPortError>
     where
@@ -68,19 +231,20 @@ impl ADIMemoryInterface {
     /// 
     /// The address where the read should be performed at has to be word aligned.
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
-    pub fn read<S, AP>(&self, debug_port: &mut AP, address: u32) -> Result<S, AccessPortError>
+    pub fn read<S, AP>(&self, debug_port: &mut AP, address: u64) -> Result<S, AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
-        if (address & S::ALIGNMENT_MASK) == 0 {
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
+        if (address & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let address = self.prepare_address(debug_port, address, std::mem::size_of::<S>())?;
+            let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
             let tar = TAR { address };
             self.write_register_ap(debug_port, csw)?;
             self.write_register_ap(debug_port, tar)?;
             let result = self.read_register_ap(debug_port, DRW::default())?;
 
-            Ok(S::to_result(result.into()))
+            Ok(S::to_result(result.data >> byte_lane_shift(address)))
         } else {
             Err(AccessPortError::MemoryNotAligned)
         }
@@ -90,15 +254,16 @@ impl ADIMemoryInterface {
     pub fn read_block_simple<S, AP>(
         &self,
         debug_port: &mut AP,
-        addr: u32,
+        addr: u64,
         data: &mut [S]
     ) -> Result<(), AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
-        if (addr & S::ALIGNMENT_MASK) == 0 {
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
+        if (addr & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let addr = self.prepare_address(debug_port, addr, std::mem::size_of_val(data))?;
+            let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
             let drw: DRW = Default::default();
 
             let unit_size = std::mem::size_of::<S>() as u32;
@@ -109,7 +274,7 @@ impl ADIMemoryInterface {
 
                 let tar = TAR { address: addr };
                 self.write_register_ap(debug_port, tar)?;
-                data[offset as usize] = S::to_result(self.read_register_ap(debug_port, drw)?.data);
+                data[offset as usize] = S::to_result(self.read_register_ap(debug_port, drw)?.data >> byte_lane_shift(addr));
             }
             Ok(())
         } else {
@@ -125,22 +290,23 @@ impl ADIMemoryInterface {
     pub fn read_block<S, AP>(
         &self,
         debug_port: &mut AP,
-        address: u32,
+        address: u64,
         data: &mut [S]
     ) -> Result<(), AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
         // In the context of this function, a word has size S. All other sizes are given in bits.
         // One byte is 8 bits.
-        if (address & S::ALIGNMENT_MASK) == 0 {
+        if (address & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let address = self.prepare_address(debug_port, address, std::mem::size_of_val(data))?;
             // Store the size of one word in bytes.
             let bytes_per_word = std::mem::size_of::<S>() as u32;
             // Calculate how many words a 32 bit value consists of.
             let f = 4 / bytes_per_word;
             // The words of size S we have to read until we can do 32 bit aligned reads.
-            let num_words_at_start = (4 - (address & 0x3)) / bytes_per_word;
+            let num_words_at_start = std::cmp::min(((4 - (address & 0x3)) & 0x3) / bytes_per_word, data.len() as u32);
             // The words of size S we have to read until we can do 32 bit aligned reads.
             let num_words_at_end = (data.len() as u32 - num_words_at_start) % f;
             // The number of 32 bit reads that are required in the second phase.
@@ -148,20 +314,26 @@ impl ADIMemoryInterface {
 
             // First we read data until we can do aligned 32 bit reads.
             // This will at a maximum be 24 bits for 8 bit transfer size and 16 bits for 16 bit transfers.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
-            self.write_register_ap(debug_port, csw)?;
-            for offset in 0..num_words_at_start {
-                let tar = TAR { address: address + offset * bytes_per_word };
-                self.write_register_ap(debug_port, tar)?;
-                data[offset as usize] = S::to_result(self.read_register_ap(debug_port, DRW::default())?.data);
+            // The sub-word CSW is only built when needed, as APB-APs do not support it.
+            if num_words_at_start > 0 {
+                let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
+                self.write_register_ap(debug_port, csw)?;
+                for offset in 0..num_words_at_start {
+                    let tar = TAR { address: address + offset * bytes_per_word };
+                    self.write_register_ap(debug_port, tar)?;
+                    data[offset as usize] = S::to_result(self.read_register_ap(debug_port, DRW::default())?.data >> byte_lane_shift(tar.address));
+                }
             }
 
             // Second we read in 32 bit reads until we have less than 32 bits left to read.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: DataSize::U32, ..Default::default() };
+            let csw = self.build_csw(DataSize::U32)?;
             self.write_register_ap(debug_port, csw)?;
             for offset in 0..num_32_bit_reads {
                 let tar = TAR { address: address + num_words_at_start * bytes_per_word + offset * 4 };
-                self.write_register_ap(debug_port, tar)?;
+                // The TAR auto increments after every transfer, but only within a 1KB block.
+                if offset == 0 || tar.address & AUTO_INCREMENT_WRAP_MASK == 0 {
+                    self.write_register_ap(debug_port, tar)?;
+                }
                 let value = self.read_register_ap(debug_port, DRW::default())?.data;
                 for i in 0..f {
                     data[(num_words_at_start + offset * f + i) as usize] = S::to_result(value >> (i * bytes_per_word * 8));
@@ -170,13 +342,15 @@ impl ADIMemoryInterface {
 
             // Lastly we read data until we can have read all the remaining data that was requested.
             // This will at a maximum be 24 bits for 8 bit transfer size and 16 bits for 16 bit transfers.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
-            self.write_register_ap(debug_port, csw)?;
-            for offset in 0..num_words_at_end {
-                let tar = TAR { address: address + num_words_at_start * bytes_per_word + num_32_bit_reads * 4 + offset * bytes_per_word };
-                self.write_register_ap(debug_port, tar)?;
-                data[(num_words_at_start + num_32_bit_reads * f + offset) as usize]
-                    = S::to_result(self.read_register_ap(debug_port, DRW::default())?.data);
+            if num_words_at_end > 0 {
+                let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
+                self.write_register_ap(debug_port, csw)?;
+                for offset in 0..num_words_at_end {
+                    let tar = TAR { address: address + num_words_at_start * bytes_per_word + num_32_bit_reads * 4 + offset * bytes_per_word };
+                    self.write_register_ap(debug_port, tar)?;
+                    data[(num_words_at_start + num_32_bit_reads * f + offset) as usize]
+                        = S::to_result(self.read_register_ap(debug_port, DRW::default())?.data >> byte_lane_shift(tar.address));
+                }
             }
             Ok(())
         } else {
@@ -191,16 +365,17 @@ impl ADIMemoryInterface {
     pub fn write<S, AP>(
         &self,
         debug_port: &mut AP,
-        addr: u32,
+        addr: u64,
         data: S
     ) -> Result<(), AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
-        if (addr & S::ALIGNMENT_MASK) == 0 {
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
-            let drw = DRW { data: data.into() };
+        if (addr & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let addr = self.prepare_address(debug_port, addr, std::mem::size_of::<S>())?;
+            let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
+            let drw = DRW { data: data.into() << byte_lane_shift(addr) };
             let tar = TAR { address: addr };
             self.write_register_ap(debug_port, csw)?;
             self.write_register_ap(debug_port, tar)?;
@@ -215,22 +390,23 @@ impl ADIMemoryInterface {
     pub fn write_block<S, AP>(
         &self,
         debug_port: &mut AP,
-        addr: u32,
+        addr: u64,
         data: &[S]
     ) -> Result<(), AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
         // In the context of this function, a word has size S. All other sizes are given in bits.
         // One byte is 8 bits.
-        if (addr & S::ALIGNMENT_MASK) == 0 {
+        if (addr & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let addr = self.prepare_address(debug_port, addr, std::mem::size_of_val(data))?;
             // Store the size of one word in bytes.
             let bytes_per_word = std::mem::size_of::<S>() as u32;
             // Calculate how many words a 32 bit value consists of.
             let f = 4 / bytes_per_word;
             // The words of size S we have to write until we can do 32 bit aligned writes.
-            let num_words_at_start = (4 - (addr & 0x3)) / bytes_per_word;
+            let num_words_at_start = std::cmp::min(((4 - (addr & 0x3)) & 0x3) / bytes_per_word, data.len() as u32);
             // The words of size S we have to write until we can do 32 bit aligned writes.
             let num_words_at_end = (data.len() as u32 - num_words_at_start) % f;
             // The number of 32 bit writes that are required in the second phase.
@@ -238,37 +414,46 @@ impl ADIMemoryInterface {
 
             // First we write data until we can do aligned 32 bit writes.
             // This will at a maximum be 24 bits for 8 bit transfer size and 16 bits for 16 bit transfers.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
-            self.write_register_ap(debug_port, csw)?;
-            for offset in 0..num_words_at_start {
-                let tar = TAR { address: addr + offset * bytes_per_word };
-                self.write_register_ap(debug_port, tar)?;
-                let drw = DRW { data: data[offset as usize].into() };
-                self.write_register_ap(debug_port, drw)?;
+            // The sub-word CSW is only built when needed, as APB-APs do not support it.
+            if num_words_at_start > 0 {
+                let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
+                self.write_register_ap(debug_port, csw)?;
+                for offset in 0..num_words_at_start {
+                    let tar = TAR { address: addr + offset * bytes_per_word };
+                    self.write_register_ap(debug_port, tar)?;
+                    let drw = DRW { data: data[offset as usize].into() << byte_lane_shift(tar.address) };
+                    self.write_register_ap(debug_port, drw)?;
+                }
             }
 
             // Second we write in 32 bit reads until we have less than 32 bits left to write.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: DataSize::U32, ..Default::default() };
+            let csw = self.build_csw(DataSize::U32)?;
             self.write_register_ap(debug_port, csw)?;
             for offset in 0..num_32_bit_writes {
-                let address = addr + num_words_at_start * bytes_per_word + offset * 4;
-                for i in 0..f {
-                    let tar = TAR { address: address + i * bytes_per_word };
+                let tar = TAR { address: addr + num_words_at_start * bytes_per_word + offset * 4 };
+                // The TAR auto increments after every transfer, but only within a 1KB block.
+                if offset == 0 || tar.address & AUTO_INCREMENT_WRAP_MASK == 0 {
                     self.write_register_ap(debug_port, tar)?;
-                    let drw = DRW { data: data[(num_words_at_start + offset * f + i) as usize].into() };
-                    self.write_register_ap(debug_port, drw)?;
                 }
+                // Pack the words of size S into one 32 bit value so a single transfer writes them all.
+                let mut value = 0;
+                for i in 0..f {
+                    value |= data[(num_words_at_start + offset * f + i) as usize].into() << (i * bytes_per_word * 8);
+                }
+                self.write_register_ap(debug_port, DRW { data: value })?;
             }
 
             // Lastly we write data until we can have written all the remaining data that was requested.
             // This will at a maximum be 24 bits for 8 bit transfer size and 16 bits for 16 bit transfers.
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
-            self.write_register_ap(debug_port, csw)?;
-            for offset in 0..num_words_at_end {
-                let tar = TAR { address: addr + num_words_at_start * bytes_per_word + num_32_bit_writes * 4 + offset * bytes_per_word };
-                self.write_register_ap(debug_port, tar)?;
-                let drw = DRW { data: data[(num_words_at_start + num_32_bit_writes * f + offset) as usize].into() };
-                self.write_register_ap(debug_port, drw)?;
+            if num_words_at_end > 0 {
+                let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
+                self.write_register_ap(debug_port, csw)?;
+                for offset in 0..num_words_at_end {
+                    let tar = TAR { address: addr + num_words_at_start * bytes_per_word + num_32_bit_writes * 4 + offset * bytes_per_word };
+                    self.write_register_ap(debug_port, tar)?;
+                    let drw = DRW { data: data[(num_words_at_start + num_32_bit_writes * f + offset) as usize].into() << byte_lane_shift(tar.address) };
+                    self.write_register_ap(debug_port, drw)?;
+                }
             }
             Ok(())
         } else {
@@ -284,22 +469,23 @@ impl ADIMemoryInterface {
     pub fn write_block_simple<S, AP>(
         &self,
         debug_port: &mut AP,
-        addr: u32,
+        addr: u64,
         data: &[S]
     ) -> Result<(), AccessPortError>
     where
         S: ToMemoryReadSize,
-        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, DRW>
+        AP: APAccess<MemoryAP, CSW> + APAccess<MemoryAP, TAR> + APAccess<MemoryAP, TAR2> + APAccess<MemoryAP, DRW>
     {
-        if (addr & S::ALIGNMENT_MASK) == 0 {
+        if (addr & u64::from(S::ALIGNMENT_MASK)) == 0 {
+            let addr = self.prepare_address(debug_port, addr, std::mem::size_of_val(data))?;
             let len = data.len() as u32;
             let unit_size = std::mem::size_of::<S>() as u32;
-            let csw: CSW = CSW { AddrInc: 1, SIZE: bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE), ..Default::default() };
+            let csw = self.build_csw(bytes_to_transfer_size(S::MEMORY_TRANSFER_SIZE))?;
             self.write_register_ap(debug_port, csw)?;
             for offset in 0..len {
                 let tar = TAR { address: addr + offset * unit_size };
                 self.write_register_ap(debug_port, tar)?;
-                let drw = DRW { data: data[offset as usize].into() };
+                let drw = DRW { data: data[offset as usize].into() << byte_lane_shift(tar.address) };
                 self.write_register_ap(debug_port, drw)?;
             }
             Ok(())
@@ -365,7 +551,7 @@ mod tests {
     fn write_u32() {
         let mut mock = MockMemoryAP::new();
         let mi = ADIMemoryInterface::new(0x0);
-        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEF as u32).is_ok());
+        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEFu32).is_ok());
         debug_assert_eq!(mock.data[0..4], [0xEF, 0xBE, 0xAD, 0xDE]);
     }
 
@@ -389,6 +575,192 @@ mod tests {
         debug_assert_eq!(mock.data[0..4], [0xEF, 0xBE, 0xAD, 0xDE]);
     }
 
+    #[test]
+    fn read_u8_uses_byte_lanes() {
+        use coresight::access_ports::memory_ap::{CSW, DataSize, DRW, MemoryAP, TAR};
+        use coresight::ap_access::APAccess;
+
+        let mut mock = MockMemoryAP::new();
+        mock.data[3] = 0xDE;
+        let port = MemoryAP::new(0x0);
+        debug_assert!(mock.write_register_ap(port, CSW { SIZE: DataSize::U8, ..Default::default() }).is_ok());
+        debug_assert!(mock.write_register_ap(port, TAR { address: 3 }).is_ok());
+        let drw: Result<DRW, _> = mock.read_register_ap(port, DRW::default());
+        debug_assert_eq!(drw.unwrap().data, 0xDE000000);
+    }
+
+    #[test]
+    fn write_u8_leaves_neighbours_untouched() {
+        let mut mock = MockMemoryAP::new();
+        mock.data[0..4].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        let mi = ADIMemoryInterface::new(0x0);
+        debug_assert!(mi.write(&mut mock, 2, 0xADu8).is_ok());
+        debug_assert_eq!(mock.data[0..4], [0x11, 0x22, 0xAD, 0x44]);
+    }
+
+    #[test]
+    fn write_u16_leaves_neighbours_untouched() {
+        let mut mock = MockMemoryAP::new();
+        mock.data[0..4].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        let mi = ADIMemoryInterface::new(0x0);
+        debug_assert!(mi.write(&mut mock, 2, 0xDEADu16).is_ok());
+        debug_assert_eq!(mock.data[0..4], [0x11, 0x22, 0xAD, 0xDE]);
+    }
+
+    #[test]
+    fn apb_ap_rejects_sub_word_accesses() {
+        use coresight::access_ports::generic_ap::APType;
+
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new_with_type(0x0, APType::AMBA_APB2_APB3);
+        let read: Result<u8, _> = mi.read(&mut mock, 0);
+        debug_assert!(read.is_err());
+        debug_assert!(mi.write(&mut mock, 0, 0xBEEFu16).is_err());
+        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEFu32).is_ok());
+        debug_assert_eq!(mock.data[0..4], [0xEF, 0xBE, 0xAD, 0xDE]);
+    }
+
+    #[test]
+    fn apb_ap_block_access_uses_word_transfers_only() {
+        use coresight::access_ports::generic_ap::APType;
+
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new_with_type(0x0, APType::AMBA_APB2_APB3);
+        debug_assert!(mi.write_block(&mut mock, 0, &[0x01u8, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]).is_ok());
+        let mut data = [0u8; 8];
+        debug_assert!(mi.read_block(&mut mock, 0, &mut data).is_ok());
+        debug_assert_eq!(data, [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        // Unaligned blocks need byte transfers, which APB-APs cannot do.
+        debug_assert!(mi.read_block(&mut mock, 1, &mut data[..4]).is_err());
+    }
+
+    #[test]
+    fn detect_uses_ap_type_from_idr() {
+        use coresight::access_ports::generic_ap::{GenericAP, IDR};
+        use coresight::ap_access::APAccess;
+
+        let mut mock = MockMemoryAP::new();
+        // An APB-AP as found on Cortex-A debug ports.
+        debug_assert!(mock.write_register_ap(GenericAP::new(0x0), IDR::from(0x4477_0002)).is_ok());
+        let mi = ADIMemoryInterface::detect(&mut mock, 0x0).unwrap();
+        let read: Result<u8, _> = mi.read(&mut mock, 0);
+        debug_assert!(read.is_err());
+
+        // A JTAG-AP is no MEM-AP at all.
+        debug_assert!(mock.write_register_ap(GenericAP::new(0x0), IDR::from(0x0476_0010)).is_ok());
+        debug_assert!(ADIMemoryInterface::detect(&mut mock, 0x0).is_err());
+    }
+
+    #[test]
+    fn apb4_ap_rejects_sub_word_accesses() {
+        use coresight::access_ports::generic_ap::APType;
+
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new_with_type(0x0, APType::AMBA_APB4_APB5);
+        let read: Result<u16, _> = mi.read(&mut mock, 0);
+        debug_assert!(read.is_err());
+        debug_assert!(mi.write(&mut mock, 0, 0xADu8).is_err());
+        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEFu32).is_ok());
+    }
+
+    #[test]
+    fn detect_with_reserved_ap_type_should_error() {
+        let mut mock = MockMemoryAP::new();
+        // A MEM-AP with the reserved type 0x9.
+        mock.set_idr(0x2477_0019);
+        debug_assert!(ADIMemoryInterface::detect(&mut mock, 0x0).is_err());
+    }
+
+    #[test]
+    fn axi_ap_uses_configured_attributes() {
+        use coresight::access_ports::generic_ap::APType;
+        use coresight::access_ports::memory_ap::{CSW, MemoryAP};
+        use coresight::ap_access::APAccess;
+
+        let mut mock = MockMemoryAP::new();
+        let mut mi = ADIMemoryInterface::new_with_type(0x0, APType::AMBA_AXI3_AXI4);
+        mi.set_axi_attributes(0b010, 0b0011);
+        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEFu32).is_ok());
+        let csw: Result<CSW, _> = mock.read_register_ap(MemoryAP::new(0x0), CSW::default());
+        let csw = csw.unwrap();
+        debug_assert_eq!(csw.PROT, 0b010);
+        debug_assert_eq!(csw.CACHE, 0b0011);
+
+        let mut mi = ADIMemoryInterface::new_with_type(0x0, APType::AMBA_AXI5);
+        mi.set_axi_attributes(0b001, 0b0010);
+        debug_assert!(mi.write(&mut mock, 0, 0xDEADBEEFu32).is_ok());
+        let csw: Result<CSW, _> = mock.read_register_ap(MemoryAP::new(0x0), CSW::default());
+        let csw = csw.unwrap();
+        debug_assert_eq!(csw.PROT, 0b001);
+        debug_assert_eq!(csw.CACHE, 0b0010);
+    }
+
+    #[test]
+    fn access_above_4gb_without_large_address_should_error() {
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new(0x0);
+        let read: Result<u32, _> = mi.read(&mut mock, 0x1_0000_0000);
+        debug_assert!(read.is_err());
+        debug_assert!(mi.write_block(&mut mock, 0xFFFF_FFFC, &([0xDEADBEEF, 0xABBABABE] as [u32; 2])).is_err());
+    }
+
+    #[test]
+    fn access_above_4gb_with_large_address() {
+        use coresight::access_ports::memory_ap::{CFG, MemoryAP, TAR2};
+        use coresight::ap_access::APAccess;
+
+        let mut mock = MockMemoryAP::new();
+        let port = MemoryAP::new(0x0);
+        debug_assert!(mock.write_register_ap(port, CFG { LA: 1, ..Default::default() }).is_ok());
+        let mut mi = ADIMemoryInterface::new(0x0);
+        debug_assert!(mi.detect_large_address(&mut mock).unwrap());
+        debug_assert!(mi.write(&mut mock, 0x8_0000_0004, 0xDEADBEEFu32).is_ok());
+        let tar2: Result<TAR2, _> = mock.read_register_ap(port, TAR2::default());
+        debug_assert_eq!(tar2.unwrap().address, 0x8);
+        debug_assert_eq!(mock.data[4..8], [0xEF, 0xBE, 0xAD, 0xDE]);
+    }
+
+    #[test]
+    fn detect_reads_large_address_support() {
+        use coresight::access_ports::memory_ap::{CFG, MemoryAP};
+        use coresight::ap_access::APAccess;
+
+        let mut mock = MockMemoryAP::new();
+        debug_assert!(mock.write_register_ap(MemoryAP::new(0x0), CFG { LA: 1, ..Default::default() }).is_ok());
+        let mi = ADIMemoryInterface::detect(&mut mock, 0x0).unwrap();
+        debug_assert!(mi.write(&mut mock, 0x1_0000_0000, 0xDEADBEEFu32).is_ok());
+        debug_assert_eq!(mock.data[0..4], [0xEF, 0xBE, 0xAD, 0xDE]);
+    }
+
+    #[test]
+    fn write_8_uses_word_transfers_between_head_and_tail() {
+        use crate::MI;
+
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new(0x0);
+        let data = [0x01u8, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A];
+        debug_assert!(mi.connect(&mut mock).write_8(1, &data).is_ok());
+        debug_assert_eq!(mock.data[1..11], data);
+        debug_assert_eq!(mock.transfers, vec![(1, 1), (2, 1), (3, 1), (4, 4), (8, 1), (9, 1), (10, 1)]);
+        debug_assert_eq!(mock.tar_writes, vec![1, 2, 3, 4, 8, 9, 10]);
+    }
+
+    #[test]
+    fn read_8_writes_tar_only_at_start_and_1kb_boundaries() {
+        use crate::MI;
+
+        let mut mock = MockMemoryAP::new();
+        for (i, byte) in mock.data[0x3FC..0x408].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let mi = ADIMemoryInterface::new(0x0);
+        let mut data = [0u8; 12];
+        debug_assert!(mi.connect(&mut mock).read_8(0x3FC, &mut data).is_ok());
+        debug_assert_eq!(data, [0x00u8, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B]);
+        debug_assert_eq!(mock.transfers, vec![(0x3FC, 4), (0x400, 4), (0x404, 4)]);
+        debug_assert_eq!(mock.tar_writes, vec![0x3FC, 0x400]);
+    }
+
     #[test]
     fn read_block_u32() {
         let mut mock = MockMemoryAP::new();
@@ -531,6 +903,40 @@ mod tests {
         debug_assert_eq!(data, [0xEF, 0xBE, 0xAD, 0xDE, 0xBE, 0xBA, 0xBA ,0xAB]);
     }
 
+    #[test]
+    fn read_block_u8_shorter_than_head() {
+        let mut mock = MockMemoryAP::new();
+        mock.data[1] = 0xBE;
+        mock.data[2] = 0xAD;
+        let mi = ADIMemoryInterface::new(0x0);
+        let mut data = [0u8; 2];
+        let read = mi.read_block(&mut mock, 1, &mut data);
+        debug_assert!(read.is_ok());
+        debug_assert_eq!(data, [0xBE, 0xAD]);
+    }
+
+    #[test]
+    fn read_block_u8_across_1kb_boundary() {
+        let mut mock = MockMemoryAP::new();
+        for i in 0..16 {
+            mock.data[0x3F8 + i] = i as u8;
+        }
+        let mi = ADIMemoryInterface::new(0x0);
+        let mut data = [0u8; 16];
+        let read = mi.read_block(&mut mock, 0x3F8, &mut data);
+        debug_assert!(read.is_ok());
+        debug_assert_eq!(data, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+    }
+
+    #[test]
+    fn write_block_u32_across_1kb_boundary() {
+        let mut mock = MockMemoryAP::new();
+        let mi = ADIMemoryInterface::new(0x0);
+        debug_assert!(mi.write_block(&mut mock, 0x3FC, &([0xDEADBEEF, 0xABBABABE] as [u32; 2])).is_ok());
+        debug_assert_eq!(mock.data[0x3FC..0x404], [0xEF, 0xBE, 0xAD, 0xDE, 0xBE, 0xBA, 0xBA ,0xAB]);
+        debug_assert_eq!(mock.data[0x000..0x004], [0x00, 0x00, 0x00, 0x00]);
+    }
+
     #[test]
     fn write_block_u32() {
         let mut mock = MockMemoryAP::new();
@@ -597,6 +1003,15 @@ mod tests {
         debug_assert_eq!(mock.data[0..11], [0x00, 0x00, 0x00, 0xEF, 0xBE, 0xAD, 0xDE, 0xBE, 0xBA, 0xBA ,0xAB]);
     }
 
+    #[test]
+    fn write_block_u8_leaves_neighbours_untouched() {
+        let mut mock = MockMemoryAP::new();
+        mock.data[0..8].copy_from_slice(&[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]);
+        let mi = ADIMemoryInterface::new(0x0);
+        debug_assert!(mi.write_block(&mut mock, 3, &([0xEF, 0xBE] as [u8; 2])).is_ok());
+        debug_assert_eq!(mock.data[0..8], [0x11, 0x22, 0x33, 0xEF, 0xBE, 0x66, 0x77, 0x88]);
+    }
+
     #[test]
     fn write_block_u8_unaligned2() {
         let mut mock = MockMemoryAP::new();
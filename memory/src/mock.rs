@@ -0,0 +1,63 @@
+use crate::{MI, ToMemoryReadSize};
+use coresight::access_ports::AccessPortError;
+use std::collections::HashMap;
+
+/// A byte addressed memory to test code that is built on top of `MI`.
+///
+/// Every access is recorded with its address and transfer size in bytes.
+/// Bytes that were never written read as zero.
+#[derive(Default)]
+pub struct MockMemory {
+    bytes: HashMap<u64, u8>,
+    pub accesses: Vec<(u64, u8)>,
+}
+
+impl MockMemory {
+    /// Places a 32 bit word at `address` without recording an access.
+    pub fn insert_word(&mut self, address: u64, value: u32) {
+        for (offset, byte) in value.to_le_bytes().iter().enumerate() {
+            self.bytes.insert(address + offset as u64, *byte);
+        }
+    }
+
+    /// Returns the 32 bit word at `address` without recording an access.
+    pub fn word(&self, address: u64) -> u32 {
+        (0..4).fold(0, |value, offset| value | (u32::from(self.byte(address + offset)) << (offset * 8)))
+    }
+
+    fn byte(&self, address: u64) -> u8 {
+        *self.bytes.get(&address).unwrap_or(&0)
+    }
+}
+
+impl MI for MockMemory {
+    fn read<S: ToMemoryReadSize>(&mut self, address: u64) -> Result<S, AccessPortError> {
+        self.accesses.push((address, S::MEMORY_TRANSFER_SIZE));
+        let value = (0..u64::from(S::MEMORY_TRANSFER_SIZE))
+            .fold(0, |value, offset| value | (u32::from(self.byte(address + offset)) << (offset * 8)));
+        Ok(S::to_result(value))
+    }
+
+    fn read_block<S: ToMemoryReadSize>(&mut self, address: u64, data: &mut [S]) -> Result<(), AccessPortError> {
+        for (index, word) in data.iter_mut().enumerate() {
+            *word = self.read(address + index as u64 * u64::from(S::MEMORY_TRANSFER_SIZE))?;
+        }
+        Ok(())
+    }
+
+    fn write<S: ToMemoryReadSize>(&mut self, addr: u64, data: S) -> Result<(), AccessPortError> {
+        self.accesses.push((addr, S::MEMORY_TRANSFER_SIZE));
+        let value: u32 = data.into();
+        for offset in 0..u64::from(S::MEMORY_TRANSFER_SIZE) {
+            self.bytes.insert(addr + offset, (value >> (offset * 8)) as u8);
+        }
+        Ok(())
+    }
+
+    fn write_block<S: ToMemoryReadSize>(&mut self, addr: u64, data: &[S]) -> Result<(), AccessPortError> {
+        for (index, word) in data.iter().enumerate() {
+            self.write(addr + index as u64 * u64::from(S::MEMORY_TRANSFER_SIZE), *word)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{MI, MI32};
+
+use coresight::access_ports::AccessPortError;
+use coresight::component::{
+    ComponentClass,
+    ComponentError,
+    ComponentId,
+    ID_REGISTERS_COUNT,
+    ID_REGISTERS_OFFSET,
+};
+use std::collections::HashSet;
+
+/// The maximum number of entries in a ROM table.
+/// The entries end at offset 0xF00 where the management registers start.
+const MAX_ROM_TABLE_ENTRIES: u32 = 0xF00 / 4;
+
+#[derive(Debug)]
+pub enum RomTableError {
+    Memory(AccessPortError),
+    Component(ComponentError),
+}
+
+impl From<AccessPortError> for RomTableError {
+    fn from(error: AccessPortError) -> Self {
+        RomTableError::Memory(error)
+    }
+}
+
+impl From<ComponentError> for RomTableError {
+    fn from(error: ComponentError) -> Self {
+        RomTableError::Component(error)
+    }
+}
+
+/// A CoreSight component and, if it is a ROM table, all the components it references.
+#[derive(Debug)]
+pub struct Component {
+    pub id: ComponentId,
+    pub children: Vec<Component>,
+    /// The referenced components that could not be read, with their base address.
+    /// They may be powered down or not be valid CoreSight components.
+    pub failed: Vec<(u32, RomTableError)>,
+}
+
+impl Component {
+    /// Calls `f` for this component and all components below it, together with their depth in the tree.
+    pub fn walk<F: FnMut(&Component, usize)>(&self, f: &mut F) {
+        self.walk_at_depth(f, 0);
+    }
+
+    fn walk_at_depth<F: FnMut(&Component, usize)>(&self, f: &mut F, depth: usize) {
+        f(self, depth);
+        for child in &self.children {
+            child.walk_at_depth(f, depth + 1);
+        }
+    }
+}
+
+/// Reads the identification registers of the component whose 4KB block starts at `base_address`.
+pub fn read_component_id<M: MI>(memory: &mut M, base_address: u32) -> Result<ComponentId, RomTableError> {
+    let mut registers = [0u32; ID_REGISTERS_COUNT];
+    memory.read_block_at(base_address + ID_REGISTERS_OFFSET, &mut registers)?;
+    Ok(ComponentId::from_id_registers(base_address, &registers)?)
+}
+
+/// Reads the component at `base_address` and, if it is a ROM table, recursively all components it references.
+///
+/// `base_address` is usually the BASEADDR read from the BASE register of a MEM-AP.
+/// Only an error reading the component at `base_address` itself is returned,
+/// referenced components that cannot be read are recorded in `Component::failed`.
+pub fn read_component_tree<M: MI>(memory: &mut M, base_address: u32) -> Result<Component, RomTableError> {
+    let mut visited = HashSet::new();
+    read_component_subtree(memory, base_address, &mut visited)
+}
+
+/// Reads a component and the components it references, skipping all components in `visited`.
+///
+/// A ROM table may reference itself or one of its parents, which would otherwise never terminate.
+fn read_component_subtree<M: MI>(
+    memory: &mut M,
+    base_address: u32,
+    visited: &mut HashSet<u32>
+) -> Result<Component, RomTableError> {
+    visited.insert(base_address);
+    let id = read_component_id(memory, base_address)?;
+    let mut children = vec![];
+    let mut failed = vec![];
+
+    if id.class == ComponentClass::RomTable {
+        for index in 0..MAX_ROM_TABLE_ENTRIES {
+            let entry: u32 = memory.read_at(base_address + index * 4)?;
+            // An all zero entry marks the end of the table.
+            if entry == 0 {
+                break;
+            }
+            // Bit 0 tells whether the entry is present, bit 1 whether it uses the 32 bit format.
+            if entry & 0x3 != 0x3 {
+                continue;
+            }
+            // The address offset is a signed value relative to the ROM table base address.
+            let child_address = base_address.wrapping_add(entry & 0xFFFFF000);
+            if visited.contains(&child_address) {
+                continue;
+            }
+            match read_component_subtree(memory, child_address, visited) {
+                Ok(child) => children.push(child),
+                Err(error) => failed.push((child_address, error)),
+            }
+        }
+    }
+
+    Ok(Component { id, children, failed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_component_tree;
+    use crate::mock::MockMemory;
+    use coresight::component::ComponentClass;
+
+    /// Places a component with the given class and part number at `base`.
+    fn add_component(memory: &mut MockMemory, base: u32, class: u8, part_number: u16) {
+        let registers = [
+            0x04, 0x00, 0x00, 0x00,
+            u32::from(part_number & 0xFF), 0xB0 | u32::from(part_number >> 8), 0x0B, 0x00,
+            0x0D, u32::from(class) << 4, 0x05, 0xB1,
+        ];
+        for (i, value) in registers.iter().enumerate() {
+            memory.insert_word(u64::from(base) + 0xFD0 + i as u64 * 4, *value);
+        }
+    }
+
+    #[test]
+    fn walk_rom_table() {
+        let mut memory = MockMemory::default();
+        add_component(&mut memory, 0xE00FF000, 0x1, 0x4C4);
+        // The SCS at 0xE000E000, a not present entry and a DWT at 0xE0001000.
+        memory.insert_word(0xE00FF000, 0xFFF0F003);
+        memory.insert_word(0xE00FF004, 0xFFF02002);
+        memory.insert_word(0xE00FF008, 0xFFF02003);
+        add_component(&mut memory, 0xE000E000, 0xE, 0x00C);
+        add_component(&mut memory, 0xE0001000, 0xE, 0x002);
+
+        let tree = read_component_tree(&mut memory, 0xE00FF000).unwrap();
+        debug_assert_eq!(tree.id.class, ComponentClass::RomTable);
+        debug_assert_eq!(tree.children.len(), 2);
+        debug_assert_eq!(tree.children[0].id.base_address, 0xE000E000);
+        debug_assert_eq!(tree.children[0].id.part_number, 0x00C);
+        debug_assert_eq!(tree.children[1].id.base_address, 0xE0001000);
+
+        let mut count = 0;
+        tree.walk(&mut |_, _| count += 1);
+        debug_assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn unreadable_children_are_recorded() {
+        let mut memory = MockMemory::default();
+        add_component(&mut memory, 0xE00FF000, 0x1, 0x4C4);
+        // An entry without a component behind it, followed by an OptimoDE component.
+        memory.insert_word(0xE00FF000, 0xFFF0F003);
+        memory.insert_word(0xE00FF004, 0xFFF02003);
+        add_component(&mut memory, 0xE0001000, 0xD, 0x123);
+
+        let tree = read_component_tree(&mut memory, 0xE00FF000).unwrap();
+        debug_assert_eq!(tree.children.len(), 1);
+        debug_assert_eq!(tree.children[0].id.class, ComponentClass::OptimoDE);
+        debug_assert_eq!(tree.failed.len(), 1);
+        debug_assert_eq!(tree.failed[0].0, 0xE000E000);
+    }
+
+    #[test]
+    fn rom_table_cycles_are_skipped() {
+        let mut memory = MockMemory::default();
+        add_component(&mut memory, 0xE00FF000, 0x1, 0x4C4);
+        add_component(&mut memory, 0xE0000000, 0x1, 0x4C4);
+        // The parent references the child ROM table, which references the parent again.
+        memory.insert_word(0xE00FF000, 0xFFF01003);
+        memory.insert_word(0xE0000000, 0x000FF003);
+
+        let tree = read_component_tree(&mut memory, 0xE00FF000).unwrap();
+        debug_assert_eq!(tree.children.len(), 1);
+        debug_assert_eq!(tree.children[0].id.base_address, 0xE0000000);
+        debug_assert!(tree.children[0].children.is_empty());
+        debug_assert!(tree.children[0].failed.is_empty());
+    }
+}
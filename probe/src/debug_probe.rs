@@ -18,6 +18,8 @@ pub enum DebugProbeError {
     NotEnoughBytesRead,
     EndpointNotFound,
     RentalInitError,
+    /// The probe cannot run the wire protocol at or below the requested speed in kHz.
+    UnsupportedSpeed(u32),
 }
 
 
@@ -37,4 +39,10 @@ pub trait DebugProbe: MI {
 
     /// Resets the target device.
     fn target_reset(&mut self) -> Result<(), DebugProbeError>;
+
+    /// Sets the clock of the wire protocol that is currently in use.
+    ///
+    /// The fastest speed the probe supports that does not exceed `speed_khz` is selected
+    /// and returned in kHz. The debug port state is kept, so this can be called while attached.
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError>;
 }
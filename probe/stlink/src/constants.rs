@@ -93,6 +93,7 @@ pub enum Status {
 }
 
 /// Map from SWD frequency in Hertz to delay loop count.
+#[derive(Debug, Clone, Copy)]
 pub enum SwdFrequencyToDelayCount {
     Hz4600000 = 0,
     Hz1800000 = 1, // Default
@@ -109,6 +110,7 @@ pub enum SwdFrequencyToDelayCount {
 }
 
 /// Map from JTAG frequency in Hertz to frequency divider.
+#[derive(Debug, Clone, Copy)]
 pub enum JTagFrequencyToDivider {
     Hz18000000 = 2,
     Hz9000000 = 4,
@@ -118,4 +120,81 @@ pub enum JTagFrequencyToDivider {
     Hz560000 = 64,
     Hz280000 = 128,
     Hz140000 = 256,
-}
\ No newline at end of file
+}
+
+impl SwdFrequencyToDelayCount {
+    /// Returns the fastest setting that does not exceed `khz`.
+    /// Returns `None` if `khz` is below the slowest setting.
+    pub fn from_khz(khz: u32) -> Option<Self> {
+        use SwdFrequencyToDelayCount::*;
+        [
+            Hz4600000, Hz1800000, Hz1200000, Hz950000, Hz650000, Hz480000,
+            Hz400000, Hz360000, Hz240000, Hz150000, Hz125000, Hz100000,
+        ].iter().cloned().find(|frequency| frequency.khz() <= khz)
+    }
+
+    /// Returns the frequency of this setting in kHz.
+    pub fn khz(self) -> u32 {
+        use SwdFrequencyToDelayCount::*;
+        match self {
+            Hz4600000 => 4600,
+            Hz1800000 => 1800,
+            Hz1200000 => 1200,
+            Hz950000 => 950,
+            Hz650000 => 650,
+            Hz480000 => 480,
+            Hz400000 => 400,
+            Hz360000 => 360,
+            Hz240000 => 240,
+            Hz150000 => 150,
+            Hz125000 => 125,
+            Hz100000 => 100,
+        }
+    }
+}
+
+impl JTagFrequencyToDivider {
+    /// Returns the fastest setting that does not exceed `khz`.
+    /// Returns `None` if `khz` is below the slowest setting.
+    pub fn from_khz(khz: u32) -> Option<Self> {
+        use JTagFrequencyToDivider::*;
+        [
+            Hz18000000, Hz9000000, Hz4500000, Hz2250000,
+            Hz1120000, Hz560000, Hz280000, Hz140000,
+        ].iter().cloned().find(|frequency| frequency.khz() <= khz)
+    }
+
+    /// Returns the frequency of this setting in kHz.
+    pub fn khz(self) -> u32 {
+        use JTagFrequencyToDivider::*;
+        match self {
+            Hz18000000 => 18000,
+            Hz9000000 => 9000,
+            Hz4500000 => 4500,
+            Hz2250000 => 2250,
+            Hz1120000 => 1120,
+            Hz560000 => 560,
+            Hz280000 => 280,
+            Hz140000 => 140,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JTagFrequencyToDivider, SwdFrequencyToDelayCount};
+
+    #[test]
+    fn swd_frequency_rounds_down() {
+        debug_assert_eq!(SwdFrequencyToDelayCount::from_khz(1000).map(|f| f.khz()), Some(950));
+        debug_assert_eq!(SwdFrequencyToDelayCount::from_khz(10_000).map(|f| f.khz()), Some(4600));
+        debug_assert!(SwdFrequencyToDelayCount::from_khz(50).is_none());
+    }
+
+    #[test]
+    fn jtag_frequency_rounds_down() {
+        debug_assert_eq!(JTagFrequencyToDivider::from_khz(1120).map(|f| f.khz()), Some(1120));
+        debug_assert_eq!(JTagFrequencyToDivider::from_khz(4000).map(|f| f.khz()), Some(2250));
+        debug_assert!(JTagFrequencyToDivider::from_khz(100).is_none());
+    }
+}
@@ -10,5 +10,6 @@ pub use crate::stlink::{
 };
 pub use crate::usb_interface::{
     STLinkUSBDevice,
+    TransferStatistics,
     get_all_plugged_devices,
 };
\ No newline at end of file
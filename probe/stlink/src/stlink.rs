@@ -17,7 +17,7 @@ use probe::debug_probe::{DebugProbe, DebugProbeError};
 use probe::protocol::WireProtocol;
 
 use crate::constants::{commands, JTagFrequencyToDivider, Status, SwdFrequencyToDelayCount};
-use crate::usb_interface::{STLinkUSBDevice, TIMEOUT};
+use crate::usb_interface::{STLinkUSBDevice, TransferStatistics, TIMEOUT};
 
 pub struct STLink {
     device: STLinkUSBDevice,
@@ -26,6 +26,8 @@ pub struct STLink {
     protocol: WireProtocol,
     current_apsel: u8,
     current_apbanksel: u8,
+    /// The MEM-AP used for all `MI` accesses.
+    memory_interface: ADIMemoryInterface,
 }
 
 impl DebugProbe for STLink {
@@ -113,7 +115,37 @@ impl DebugProbe for STLink {
             TIMEOUT,
         )?;
         self.protocol = protocol;
-        Self::check_status(&buf)
+        Self::check_status(&buf)?;
+
+        // Access memory through AP 0 according to its bus type.
+        // If AP 0 is no MEM-AP, the AHB defaults are kept until `select_memory_ap` is called.
+        if let Ok(memory_interface) = ADIMemoryInterface::detect(self, 0) {
+            self.memory_interface = memory_interface;
+        }
+        Ok(())
+    }
+
+    /// Sets the SWD or JTAG clock, depending on the protocol selected in `attach`.
+    ///
+    /// The ST-Link V3 picks the closest supported frequency itself, which is the one returned.
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        if self.hw_version >= 3 {
+            return self.set_communication_frequency(speed_khz);
+        }
+        match self.protocol {
+            WireProtocol::Swd => {
+                let frequency = SwdFrequencyToDelayCount::from_khz(speed_khz)
+                    .ok_or(DebugProbeError::UnsupportedSpeed(speed_khz))?;
+                self.set_swd_frequency(frequency)?;
+                Ok(frequency.khz())
+            },
+            WireProtocol::Jtag => {
+                let frequency = JTagFrequencyToDivider::from_khz(speed_khz)
+                    .ok_or(DebugProbeError::UnsupportedSpeed(speed_khz))?;
+                self.set_jtag_frequency(frequency)?;
+                Ok(frequency.khz())
+            },
+        }
     }
 
     /// Leave debug mode.
@@ -275,32 +307,36 @@ impl Drop for STLink {
 
 impl MI for STLink
 {
-    fn read<S: ToMemoryReadSize>(&mut self, address: u32) -> Result<S, AccessPortError> {
-        ADIMemoryInterface::new(0).read(self, address)
+    fn read<S: ToMemoryReadSize>(&mut self, address: u64) -> Result<S, AccessPortError> {
+        let memory_interface = self.memory_interface;
+        memory_interface.read(self, address)
     }
 
     fn read_block<S: ToMemoryReadSize>(
         &mut self,
-        address: u32,
+        address: u64,
         data: &mut [S]
     ) -> Result<(), AccessPortError> {
-        ADIMemoryInterface::new(0).read_block(self, address, data)
+        let memory_interface = self.memory_interface;
+        memory_interface.read_block(self, address, data)
     }
 
     fn write<S: ToMemoryReadSize>(
         &mut self,
-        addr: u32,
+        addr: u64,
         data: S
     ) -> Result<(), AccessPortError> {
-        ADIMemoryInterface::new(0).write(self, addr, data)
+        let memory_interface = self.memory_interface;
+        memory_interface.write(self, addr, data)
     }
 
     fn write_block<S: ToMemoryReadSize>(
         &mut self,
-        addr: u32,
+        addr: u64,
         data: &[S]
     ) -> Result<(), AccessPortError> {
-        ADIMemoryInterface::new(0).write_block(self, addr, data)
+        let memory_interface = self.memory_interface;
+        memory_interface.write_block(self, addr, data)
     }
 }
 
@@ -336,6 +372,7 @@ impl STLink {
             protocol: WireProtocol::Swd,
             current_apsel: 0x0000,
             current_apbanksel: 0x00,
+            memory_interface: ADIMemoryInterface::new(0),
         };
 
         stlink.init()?;
@@ -343,6 +380,37 @@ impl STLink {
         Ok(stlink)
     }
 
+    /// Selects the MEM-AP used for all `MI` accesses.
+    ///
+    /// The bus type of the AP is read from its IDR, so APB-APs and AXI-APs are accessed correctly.
+    /// Returns `AccessPortError::NotAMemoryAP` if the AP is not a MEM-AP.
+    pub fn select_memory_ap(&mut self, access_port_number: u8) -> Result<(), AccessPortError> {
+        self.memory_interface = ADIMemoryInterface::detect(self, access_port_number)?;
+        Ok(())
+    }
+
+    /// Reads a banked DP register, e.g. TARGETID in bank 2 of a DPv2.
+    ///
+    /// DPBANKSEL in SELECT is set for the read and reset to bank 0 afterwards.
+    /// The selected AP and AP bank are kept, so the SELECT cache stays valid.
+    pub fn read_dp_banked(&mut self, bank: u8, addr: u16) -> Result<u32, DebugProbeError> {
+        let select = (u32::from(self.current_apsel) << 24) | (u32::from(self.current_apbanksel) << 4);
+        self.write_register(Self::DP_PORT, 0x008, select | u32::from(bank & 0x0F))?;
+        let result = self.read_register(Self::DP_PORT, addr);
+        self.write_register(Self::DP_PORT, 0x008, select)?;
+        result
+    }
+
+    /// Returns the counters of the USB traffic exchanged with the ST-Link so far.
+    pub fn statistics(&self) -> TransferStatistics {
+        self.device.statistics()
+    }
+
+    /// Resets the counters of the USB traffic, e.g. to measure a single operation.
+    pub fn reset_statistics(&mut self) {
+        self.device.reset_statistics()
+    }
+
     /// Reads the target voltage.
     /// For the china fake variants this will always read a nonzero value!
     pub fn get_target_voltage(&mut self) -> Result<f32, DebugProbeError> {
@@ -421,11 +489,7 @@ impl STLink {
     ) -> Result<(), DebugProbeError> {
         let mut buf = [0; 2];
         self.device.write(
-            vec![
-                commands::JTAG_COMMAND,
-                commands::SWD_SET_FREQ,
-                frequency as u8,
-            ],
+            set_frequency_command(commands::SWD_SET_FREQ, frequency as u16),
             &[],
             &mut buf,
             TIMEOUT,
@@ -440,11 +504,7 @@ impl STLink {
     ) -> Result<(), DebugProbeError> {
         let mut buf = [0; 2];
         self.device.write(
-            vec![
-                commands::JTAG_COMMAND,
-                commands::JTAG_SET_FREQ,
-                frequency as u8,
-            ],
+            set_frequency_command(commands::JTAG_SET_FREQ, frequency as u16),
             &[],
             &mut buf,
             TIMEOUT,
@@ -452,6 +512,25 @@ impl STLink {
         Self::check_status(&buf)
     }
 
+    /// Sets the SWD or JTAG frequency of an ST-Link V3, which replaces the divider based commands.
+    /// Returns the frequency in kHz that was actually applied.
+    pub fn set_communication_frequency(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        let protocol = match self.protocol {
+            WireProtocol::Swd => commands::JTAG_STLINK_SWD_COM,
+            WireProtocol::Jtag => commands::JTAG_STLINK_JTAG_COM,
+        };
+        let mut buf = [0; 8];
+        self.device.write(
+            set_communication_frequency_command(protocol, speed_khz),
+            &[],
+            &mut buf,
+            TIMEOUT,
+        )?;
+        Self::check_status(&buf)?;
+        // Unwrap is ok!
+        Ok(buf[4..8].pread(0).unwrap())
+    }
+
     pub fn open_ap(&mut self, apsel: impl AccessPort) -> Result<(), DebugProbeError> {
         if self.jtag_version < Self::MIN_JTAG_VERSION_MULTI_AP {
             Err(DebugProbeError::JTagDoesNotSupportMultipleAP)
@@ -521,3 +600,49 @@ impl STLink {
         }
     }
 }
+
+/// Builds a SWD_SET_FREQ or JTAG_SET_FREQ command.
+///
+/// The delay count or divider is sent as a 16 bit little endian value, as the slowest JTAG divider is 256.
+fn set_frequency_command(command: u8, value: u16) -> Vec<u8> {
+    vec![commands::JTAG_COMMAND, command, value as u8, (value >> 8) as u8]
+}
+
+/// Builds a SET_COM_FREQ command for the ST-Link V3.
+///
+/// The frequency is sent in kHz as a 32 bit little endian value.
+fn set_communication_frequency_command(protocol: u8, speed_khz: u32) -> Vec<u8> {
+    vec![
+        commands::JTAG_COMMAND,
+        commands::SET_COM_FREQ,
+        protocol,
+        0,
+        speed_khz as u8,
+        (speed_khz >> 8) as u8,
+        (speed_khz >> 16) as u8,
+        (speed_khz >> 24) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_communication_frequency_command, set_frequency_command};
+    use crate::constants::{commands, JTagFrequencyToDivider};
+
+    #[test]
+    fn slowest_jtag_divider_is_sent_as_16_bit() {
+        let frequency = JTagFrequencyToDivider::from_khz(200).unwrap();
+        debug_assert_eq!(
+            set_frequency_command(commands::JTAG_SET_FREQ, frequency as u16),
+            vec![commands::JTAG_COMMAND, commands::JTAG_SET_FREQ, 0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn communication_frequency_is_sent_in_khz() {
+        debug_assert_eq!(
+            set_communication_frequency_command(commands::JTAG_STLINK_JTAG_COM, 24000),
+            vec![commands::JTAG_COMMAND, commands::SET_COM_FREQ, 0x01, 0x00, 0xC0, 0x5D, 0x00, 0x00]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use lazy_static::lazy_static;
 use libusb::{Context, Device, DeviceHandle, Error};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use std::collections::HashMap;
 
@@ -57,6 +57,21 @@ impl STLinkInfo {
     }
 }
 
+/// Counters for the USB traffic exchanged with an ST-Link.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransferStatistics {
+    /// The number of commands sent to the probe.
+    pub commands: usize,
+    /// The number of commands that failed on the USB level.
+    pub failed_commands: usize,
+    /// The number of bytes sent to the probe, including the command packets.
+    pub bytes_written: usize,
+    /// The number of bytes received from the probe.
+    pub bytes_read: usize,
+    /// The time spent waiting for USB transfers to complete.
+    pub transfer_time: Duration,
+}
+
 rental! {
     pub mod rent {
         use super::*;
@@ -75,6 +90,7 @@ pub use rent::STLinkUSBDeviceRenter;
 pub struct STLinkUSBDevice {
     renter: STLinkUSBDeviceRenter,
     info: STLinkInfo,
+    statistics: TransferStatistics,
 }
 
 fn usb_match<'a>(device: &Device<'a>) -> bool {
@@ -160,6 +176,7 @@ impl STLinkUSBDevice {
         let usb_stlink = Self {
             renter,
             info,
+            statistics: Default::default(),
         };
 
         Ok(usb_stlink)
@@ -178,6 +195,40 @@ impl STLinkUSBDevice {
     /// In a second step `write_data` is transmitted.
     /// And lastly, data will be read back until `read_data` is filled.
     pub fn write(
+        &mut self,
+        cmd: Vec<u8>,
+        write_data: &[u8],
+        read_data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(), DebugProbeError> {
+        let instant = Instant::now();
+        let result = self.transfer(cmd, write_data, read_data, timeout);
+
+        self.statistics.commands += 1;
+        self.statistics.transfer_time += instant.elapsed();
+        if result.is_ok() {
+            self.statistics.bytes_written += CMD_LEN + write_data.len();
+            self.statistics.bytes_read += read_data.len();
+        } else {
+            self.statistics.failed_commands += 1;
+        }
+
+        result
+    }
+
+    /// Returns the counters of the USB traffic since the device was opened or the counters were last reset.
+    pub fn statistics(&self) -> TransferStatistics {
+        self.statistics
+    }
+
+    /// Resets all counters of the USB traffic to zero.
+    pub fn reset_statistics(&mut self) {
+        self.statistics = Default::default();
+    }
+
+    /// Performs the three phases of a `write` without updating the statistics.
+    /// Internal helper.
+    fn transfer(
         &mut self,
         mut cmd: Vec<u8>,
         write_data: &[u8],